@@ -3,7 +3,9 @@ use crate::systems::{
     drone::Drone,
     faction::Faction,
     health::Health,
-    unit::{TargetPosition, Unit, UnitSize},
+    stats::CombatStats,
+    status_effect::StatusEffects,
+    unit::{TargetPosition, Unit, UnitClass, UnitSize, Velocity},
     walker::Walker,
 };
 use bevy::prelude::*;
@@ -18,6 +20,10 @@ pub struct UnitBundle {
     pub faction: Faction,
     pub abilities: UnitAbilities,
     pub size: UnitSize,
+    pub class: UnitClass,
+    pub stats: CombatStats,
+    pub status_effects: StatusEffects,
+    pub velocity: Velocity,
 }
 
 #[derive(Bundle, Default)]
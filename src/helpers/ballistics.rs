@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+/// Velocity a projectile launched at `speed` from `origin` needs to land on `target` while
+/// being pulled down by `gravity`. Uses the lower of the two possible arcs, and returns `None`
+/// if the target can't be reached at that speed
+pub fn ballistic_launch_velocity(
+    origin: Vec3,
+    target: Vec3,
+    speed: f32,
+    gravity: f32,
+) -> Option<Vec3> {
+    let difference = target - origin;
+    let horizontal = Vec3::new(difference.x, 0., difference.z);
+    let distance = horizontal.length();
+    if gravity <= 0. || distance <= f32::EPSILON {
+        return None;
+    }
+
+    let speed_squared = speed * speed;
+    let discriminant = speed_squared * speed_squared
+        - gravity * (gravity * distance * distance + 2. * difference.y * speed_squared);
+    if discriminant < 0. {
+        return None;
+    }
+
+    let angle = ((speed_squared - discriminant.sqrt()) / (gravity * distance)).atan();
+    Some(horizontal / distance * speed * angle.cos() + Vec3::unit_y() * speed * angle.sin())
+}
+
+/// Velocity that launches a projectile at 45 degrees towards `target`, which gives the longest
+/// possible range. Used when the target is too far away to actually be reached
+pub fn max_range_launch_velocity(origin: Vec3, target: Vec3, speed: f32) -> Vec3 {
+    let mut horizontal = target - origin;
+    horizontal.y = 0.;
+    if horizontal.length_squared() <= f32::EPSILON {
+        return Vec3::unit_y() * speed;
+    }
+
+    let component = speed * std::f32::consts::FRAC_1_SQRT_2;
+    horizontal.normalize() * component + Vec3::unit_y() * component
+}
+
+/// Point where a projectile fired from `origin` at `speed` meets a target that is at `target`
+/// and keeps moving at `target_velocity`. Returns `None` if the projectile can't catch up
+pub fn intercept_point(
+    origin: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    speed: f32,
+) -> Option<Vec3> {
+    let difference = target - origin;
+    // Solves |difference + target_velocity * t| = speed * t for the earliest positive t
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2. * difference.dot(target_velocity);
+    let c = difference.length_squared();
+
+    let time = if a.abs() <= f32::EPSILON {
+        // Target moves as fast as the projectile
+        if b >= 0. {
+            return None;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4. * a * c;
+        if discriminant < 0. {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let (t1, t2) = ((-b - root) / (2. * a), (-b + root) / (2. * a));
+        match (t1 > 0., t2 > 0.) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            (false, false) => return None,
+        }
+    };
+
+    Some(target + target_velocity * time)
+}
+
+/// Randomly deviates the aim from `origin` to `target` by up to `spread` radians
+pub fn scatter(origin: Vec3, target: Vec3, spread: f32, rng: &mut impl Rng) -> Vec3 {
+    let difference = target - origin;
+    if spread <= 0. || difference.length_squared() <= f32::EPSILON {
+        return target;
+    }
+
+    let forward = difference.normalize();
+    let mut perpendicular = forward.cross(Vec3::unit_y());
+    if perpendicular.length_squared() <= f32::EPSILON {
+        perpendicular = Vec3::unit_x();
+    }
+
+    // Tilt away from the aim, then spin the tilt around it
+    let tilt = Quat::from_axis_angle(perpendicular.normalize(), rng.gen_range(0., spread));
+    let spin = Quat::from_axis_angle(forward, rng.gen_range(0., std::f32::consts::PI * 2.));
+    origin + spin.mul_vec3(tilt.mul_vec3(difference))
+}
@@ -0,0 +1,23 @@
+use crate::systems::camera::CameraFollow;
+use bevy::prelude::*;
+
+/// Camera that flat sprites floating over units, like status icons and cast bars, turn towards
+pub struct BillboardCamera {
+    pub transform: Transform,
+    pub right: Vec3, // Direction rows of sprites are laid out along on screen
+}
+
+impl BillboardCamera {
+    pub fn find(camera_query: &Query<(&CameraFollow, &Transform)>) -> Option<Self> {
+        camera_query.iter().next().map(|(_, transform)| Self {
+            transform: *transform,
+            right: transform.rotation.mul_vec3(Vec3::unit_x()),
+        })
+    }
+
+    /// Moves the sprite to `position` and turns it to face the camera
+    pub fn place(&self, transform: &mut Transform, position: Vec3) {
+        transform.translation = position;
+        transform.look_at(self.transform.translation, Vec3::unit_y());
+    }
+}
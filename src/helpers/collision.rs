@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+/// Returns how far along the segment from `start` to `end` (between 0 and 1) it first touches
+/// the sphere, or `None` if it doesn't touch it at all
+pub fn segment_sphere_intersection(
+    start: Vec3,
+    end: Vec3,
+    center: Vec3,
+    radius: f32,
+) -> Option<f32> {
+    let segment = end - start;
+    let offset = start - center;
+
+    let c = offset.dot(offset) - radius * radius;
+    // The segment starts inside the sphere
+    if c <= 0. {
+        return Some(0.);
+    }
+
+    let a = segment.dot(segment);
+    if a <= f32::EPSILON {
+        return None;
+    }
+
+    let b = offset.dot(segment);
+    let discriminant = b * b - a * c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / a;
+    if (0. ..=1.).contains(&t) {
+        Some(t)
+    } else {
+        None
+    }
+}
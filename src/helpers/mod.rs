@@ -1,3 +1,6 @@
+pub mod ballistics;
+pub mod billboard;
+pub mod collision;
 pub mod font;
 pub mod movement;
 pub mod shapes;
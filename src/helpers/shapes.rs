@@ -95,3 +95,40 @@ pub fn set_circle_attributes(mesh: &mut Mesh) {
     mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
 }
+
+/// Flat circular sector of radius 1, facing -z. Use `set_sector_attributes` to change its shape
+pub fn sector_mesh() -> Mesh {
+    let mut mesh = Mesh::new(bevy::render::pipeline::PrimitiveTopology::TriangleList);
+    set_sector_attributes(&mut mesh, std::f32::consts::PI * 2.);
+
+    mesh
+}
+
+/// Makes the mesh a sector spanning `angle` radians around -z. A full turn gives a circle
+pub fn set_sector_attributes(mesh: &mut Mesh, angle: f32) {
+    const SEGMENTS: u32 = 32;
+
+    let mut positions: Vec<[f32; 3]> = vec![[0.0, 0.0, 0.0]];
+    for i in 0..=SEGMENTS {
+        let theta = -angle * 0.5 + angle * i as f32 / SEGMENTS as f32;
+        positions.push([theta.sin(), 0.0, -theta.cos()]);
+    }
+
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    for _ in 0..positions.len() {
+        normals.push([0.0, 0.0, 0.0]);
+        uvs.push([0.0, 0.0]);
+    }
+
+    // Triangles fanning out from the center, wound to face up
+    let mut indices = Vec::new();
+    for i in 0..SEGMENTS {
+        indices.extend_from_slice(&[0, i + 2, i + 1]);
+    }
+
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+}
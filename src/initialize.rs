@@ -1,18 +1,19 @@
-use crate::bundles::*;
+use crate::prefabs::*;
 use crate::systems::{
-    ability::*,
     attack,
-    camera::{CameraFollow, CanHaveCamera},
+    bullet::{Explosion, Projectile, Trajectory},
+    camera::CameraFollow,
+    faction::FactionId,
     health::*,
-    selection::SelectableBuilder,
-    unit::*,
+    status_effect::*,
 };
-use bevy::{math::Quat, prelude::*};
+use bevy::prelude::*;
 use bevy_contrib_colors::Tailwind;
 use bevy_mod_picking::*;
 
 pub fn setup(
     commands: &mut Commands,
+    prefabs: Res<PrefabResource>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
@@ -32,38 +33,71 @@ pub fn setup(
         })
         .spawn(UiCameraBundle::default());
 
-    let walker_mesh = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
-    let big_walker_mesh = meshes.add(Mesh::from(shape::Cube { size: 5.0 }));
-    let material = materials.add(Tailwind::RED400.into());
     for i in 0..5 {
         for j in 0..5 {
+            // The back row carries artillery
+            let ranged = if j == 0 {
+                attack::Ranged {
+                    weapons: vec![attack::Weapon {
+                        kind: attack::RangedWeapon::Projectile(Projectile {
+                            trajectory: Trajectory::Ballistic { gravity: 20. },
+                            damage: Damage::new(2, DamageType::Explosive),
+                            explosion: Some(Explosion {
+                                radius: 3.,
+                                detonate_at_target: true,
+                                ..Default::default()
+                            }),
+                            // The blast leaves survivors dazed
+                            on_hit: Some(StatusEffect {
+                                id: "dazed",
+                                kind: EffectKind::Stun,
+                                duration: 0.5,
+                                stacking: Stacking::Refresh,
+                            }),
+                        }),
+                        range: 35.,
+                        // Shells can't be lobbed at enemies that are too close
+                        min_range: Some(8.),
+                        cooldown: 2.5,
+                        // Shells land all around the target, but the blast makes up for it
+                        spread: 0.08,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }
+            } else {
+                attack::Ranged::default()
+            };
+
             create_walker(
                 commands,
-                walker_mesh.clone(),
-                material.clone(),
+                &prefabs,
                 Vec3::new(i as f32 * 5.0 - 10.0, 1.0, j as f32 * 5.0 - 10.0),
+                FactionId::PLAYER,
+                ranged,
             );
         }
     }
 
     create_tp_healer(
         commands,
-        big_walker_mesh,
-        materials.add(Tailwind::RED700.into()),
+        &prefabs,
         Vec3::new(20.0, 0.0, 20.0),
+        FactionId::PLAYER,
     );
 
-    let drone_mesh = meshes.add(Mesh::from(shape::Icosphere {
-        subdivisions: 4,
-        radius: 1.0,
-    }));
     create_drone(
         commands,
-        drone_mesh.clone(),
-        material.clone(),
+        &prefabs,
         Vec3::new(10.0, 20.0, 5.0),
+        FactionId::PLAYER,
+    );
+    let camera_holder = create_drone(
+        commands,
+        &prefabs,
+        Vec3::new(-25.0, 60.0, 0.0),
+        FactionId::PLAYER,
     );
-    let camera_holder = create_drone(commands, drone_mesh, material, Vec3::new(-25.0, 60.0, 0.0));
 
     commands
         .spawn(Camera3dBundle {
@@ -75,110 +109,3 @@ pub fn setup(
             ..Default::default()
         });
 }
-
-fn create_walker(
-    commands: &mut Commands,
-    mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
-    position: Vec3,
-) -> Entity {
-    commands
-        .spawn(PbrBundle {
-            mesh,
-            material,
-            transform: Transform::from_translation(Vec3::new(position.x, 1.0, position.z)),
-            ..Default::default()
-        })
-        .with(SelectableBuilder::default())
-        .with(CanHaveCamera::default())
-        .with_bundle(UnitBundle::default())
-        .with_bundle(WalkerBundle::default())
-        .with(attack::Ranged::default())
-        .current_entity()
-        .unwrap()
-}
-
-fn create_tp_healer(
-    commands: &mut Commands,
-    mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
-    position: Vec3,
-) -> Entity {
-    commands
-        .spawn(PbrBundle {
-            mesh,
-            material,
-            transform: Transform::from_translation(Vec3::new(position.x, 5.0, position.z)),
-            ..Default::default()
-        })
-        .with(SelectableBuilder::default())
-        .with(CanHaveCamera::default())
-        .with_bundle(UnitBundle {
-            unit: Unit {
-                speed: 30.0,
-                ..Default::default()
-            },
-            size: UnitSize(5.),
-            health: Health::new(10),
-            abilities: UnitAbilities {
-                abilities: vec![
-                    AbilityButton {
-                        name: "Teleport".to_string(),
-                        id: "teleport",
-                        callback: |_commands, mut ability, _buttons, callback_data| {
-                            ability.ability = Ability::Teleport(callback_data.entity.unwrap());
-                        },
-                    },
-                    AbilityButton {
-                        name: "Heal".to_string(),
-                        id: "heal-unit",
-                        callback: |_commands, mut ability, _buttons, _callback_data| {
-                            ability.ability = Ability::HealUnit;
-                        },
-                    },
-                    AbilityButton {
-                        name: "Heal area".to_string(),
-                        id: "heal-area",
-                        callback: |_commands, mut ability, _buttons, _callback_data| {
-                            ability.ability = Ability::HealArea;
-                        },
-                    },
-                ],
-            },
-            ..UnitBundle::default()
-        })
-        .with_bundle(WalkerBundle::default())
-        .current_entity()
-        .unwrap()
-}
-
-fn create_drone(
-    commands: &mut Commands,
-    mesh: Handle<Mesh>,
-    material: Handle<StandardMaterial>,
-    position: Vec3,
-) -> Entity {
-    commands
-        .spawn(PbrBundle {
-            mesh,
-            material,
-            transform: Transform::from_matrix(Mat4::from_rotation_translation(
-                Quat::from_xyzw(-0.3, -0.5, -0.3, 0.5).normalize(),
-                position,
-            )),
-            ..Default::default()
-        })
-        .with(SelectableBuilder::default())
-        .with(CanHaveCamera::default())
-        .with_bundle(UnitBundle {
-            unit: Unit {
-                speed: 30.0,
-                ..Default::default()
-            },
-            ..UnitBundle::default()
-        })
-        .with_bundle(DroneBundle::default())
-        .with(attack::Ranged::default())
-        .current_entity()
-        .unwrap()
-}
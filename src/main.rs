@@ -2,8 +2,9 @@
 
 #[allow(unused_imports)]
 use crate::systems::{
-    ability, aliens, attack, axes, bullet, camera, debug, drag_selection, drone, health,
-    health_numbers, selection, selection_circle, target_indicator, time, ui, unit, walker,
+    ability, aliens, attack, aura, axes, beam, bullet, camera, casting, death, debug,
+    drag_selection, drone, faction, healer, health, health_numbers, reticle, selection,
+    selection_circle, stats, status_effect, summon, target_indicator, time, ui, unit, walker,
 };
 use bevy::prelude::*;
 use bevy_mod_picking::*;
@@ -11,6 +12,7 @@ use bevy_mod_picking::*;
 mod bundles;
 mod helpers;
 mod initialize;
+mod prefabs;
 mod systems;
 
 fn main() {
@@ -25,6 +27,8 @@ fn main() {
         .add_plugins(DefaultPlugins)
         // .add_plugin(debug::DebugPlugin)
         .add_plugin(time::TimePlugin)
+        .add_plugin(faction::FactionPlugin)
+        .add_plugin(prefabs::PrefabPlugin)
         .add_plugin(PickingPlugin)
         .add_plugin(selection::SelectionPlugin)
         .add_plugin(drag_selection::DragSelectionPlugin)
@@ -37,11 +41,20 @@ fn main() {
         .add_plugin(target_indicator::TargetIndicatorPlugin)
         .add_plugin(attack::AttackPlugin)
         .add_plugin(bullet::BulletPlugin)
+        .add_plugin(beam::BeamPlugin)
         .add_plugin(ui::UIPlugin)
         .add_plugin(ability::AbilityPlugin)
+        .add_plugin(casting::CastingPlugin)
+        .add_plugin(healer::HealerPlugin)
+        .add_plugin(summon::SummonPlugin)
         .add_startup_system(initialize::setup.system())
         .add_plugin(health::HealthPlugin)
+        .add_plugin(death::DeathPlugin)
         .add_plugin(health_numbers::HealthNumbersPlugin)
+        .add_plugin(stats::StatsPlugin)
+        .add_plugin(status_effect::StatusEffectPlugin)
+        .add_plugin(aura::AuraPlugin)
         .add_plugin(selection_circle::SelectionCirclePlugin)
+        .add_plugin(reticle::ReticlePlugin)
         .run();
 }
@@ -0,0 +1,255 @@
+use crate::bundles::*;
+use crate::systems::{
+    ability::*,
+    attack,
+    aura::{Aura, Auras},
+    beam::Beam,
+    bullet::{Explosion, Projectile, Trajectory},
+    camera::CanHaveCamera,
+    death::*,
+    faction::{Faction, FactionId},
+    healer,
+    health::*,
+    selection::SelectableBuilder,
+    status_effect::*,
+    unit::*,
+};
+use bevy::{math::Quat, prelude::*};
+use bevy_contrib_colors::Tailwind;
+
+// Drones are never spawned closer to the ground than this
+const DRONE_MIN_HEIGHT: f32 = 10.;
+
+/// Meshes and materials of the units that can be spawned from a prefab
+pub struct PrefabResource {
+    walker_mesh: Handle<Mesh>,
+    healer_mesh: Handle<Mesh>,
+    drone_mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    healer_material: Handle<StandardMaterial>,
+}
+
+impl FromResources for PrefabResource {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut meshes = resources.get_mut::<Assets<Mesh>>().unwrap();
+        let mut materials = resources.get_mut::<Assets<StandardMaterial>>().unwrap();
+        PrefabResource {
+            walker_mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            healer_mesh: meshes.add(Mesh::from(shape::Cube { size: 5.0 })),
+            drone_mesh: meshes.add(Mesh::from(shape::Icosphere {
+                subdivisions: 4,
+                radius: 1.0,
+            })),
+            material: materials.add(Tailwind::RED400.into()),
+            healer_material: materials.add(Tailwind::RED700.into()),
+        }
+    }
+}
+
+pub struct PrefabPlugin;
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<PrefabResource>();
+    }
+}
+
+/// Kind of unit that can be spawned both at startup and by summoning abilities. Every one of
+/// them is selectable
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Prefab {
+    Walker,
+    Drone,
+    Healer,
+}
+
+impl Prefab {
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        prefabs: &PrefabResource,
+        position: Vec3,
+        faction: FactionId,
+    ) -> Entity {
+        match self {
+            Prefab::Walker => create_walker(
+                commands,
+                prefabs,
+                position,
+                faction,
+                attack::Ranged::default(),
+            ),
+            Prefab::Drone => create_drone(
+                commands,
+                prefabs,
+                Vec3::new(position.x, position.y.max(DRONE_MIN_HEIGHT), position.z),
+                faction,
+            ),
+            Prefab::Healer => create_tp_healer(commands, prefabs, position, faction),
+        }
+    }
+}
+
+pub fn create_walker(
+    commands: &mut Commands,
+    prefabs: &PrefabResource,
+    position: Vec3,
+    faction: FactionId,
+    ranged: attack::Ranged,
+) -> Entity {
+    commands
+        .spawn(PbrBundle {
+            mesh: prefabs.walker_mesh.clone(),
+            material: prefabs.material.clone(),
+            transform: Transform::from_translation(Vec3::new(position.x, 1.0, position.z)),
+            ..Default::default()
+        })
+        .with(SelectableBuilder::default())
+        .with(CanHaveCamera::default())
+        .with_bundle(UnitBundle {
+            faction: Faction::new(faction),
+            ..UnitBundle::default()
+        })
+        .with_bundle(WalkerBundle::default())
+        .with(ranged)
+        .current_entity()
+        .unwrap()
+}
+
+pub fn create_tp_healer(
+    commands: &mut Commands,
+    prefabs: &PrefabResource,
+    position: Vec3,
+    faction: FactionId,
+) -> Entity {
+    commands
+        .spawn(PbrBundle {
+            mesh: prefabs.healer_mesh.clone(),
+            material: prefabs.healer_material.clone(),
+            transform: Transform::from_translation(Vec3::new(position.x, 5.0, position.z)),
+            ..Default::default()
+        })
+        .with(SelectableBuilder::default())
+        .with(CanHaveCamera::default())
+        .with_bundle(UnitBundle {
+            unit: Unit {
+                speed: 30.0,
+                ..Default::default()
+            },
+            size: UnitSize(5.),
+            class: UnitClass::Support,
+            faction: Faction::new(faction),
+            health: Health::new(10),
+            abilities: UnitAbilities::new(vec![
+                healer::TELEPORT,
+                healer::HEAL_UNIT,
+                healer::HEAL_AREA,
+                healer::HEALING_WAVE,
+                healer::BARRIER,
+                healer::MENDING_BEAM,
+                healer::DEPLOY_DRONE,
+            ]),
+            ..UnitBundle::default()
+        })
+        .with_bundle(WalkerBundle::default())
+        .with(Armor(1))
+        .with(Energy::new(100., 5.))
+        // Slowly regenerates nearby allies
+        .with(Auras::new(vec![Aura {
+            radius: 10.,
+            affects: Affects::Allies,
+            effect: StatusEffect {
+                id: "mending-aura",
+                kind: EffectKind::HealOverTime {
+                    amount: 1,
+                    interval: 2.,
+                },
+                duration: 1.,
+                stacking: Stacking::Refresh,
+            },
+        }]))
+        .with(DeathAnimation {
+            style: DeathStyle::Fade,
+            duration: 2.,
+        })
+        .current_entity()
+        .unwrap()
+}
+
+pub fn create_drone(
+    commands: &mut Commands,
+    prefabs: &PrefabResource,
+    position: Vec3,
+    faction: FactionId,
+) -> Entity {
+    commands
+        .spawn(PbrBundle {
+            mesh: prefabs.drone_mesh.clone(),
+            material: prefabs.material.clone(),
+            transform: Transform::from_matrix(Mat4::from_rotation_translation(
+                Quat::from_xyzw(-0.3, -0.5, -0.3, 0.5).normalize(),
+                position,
+            )),
+            ..Default::default()
+        })
+        .with(SelectableBuilder::default())
+        .with(CanHaveCamera::default())
+        .with_bundle(UnitBundle {
+            unit: Unit {
+                speed: 30.0,
+                ..Default::default()
+            },
+            class: UnitClass::Drone,
+            faction: Faction::new(faction),
+            ..UnitBundle::default()
+        })
+        .with_bundle(DroneBundle::default())
+        .with(attack::Ranged {
+            weapons: vec![
+                // A rapid-fire laser that goes through every enemy in its way
+                attack::Weapon {
+                    kind: attack::RangedWeapon::Beam(Beam {
+                        damage: Damage::new(1, DamageType::Energy),
+                        piercing: true,
+                        on_hit: None,
+                    }),
+                    cooldown: 0.4,
+                    ..Default::default()
+                },
+                // And a slow missile for groups of enemies
+                attack::Weapon {
+                    kind: attack::RangedWeapon::Projectile(Projectile {
+                        damage: Damage::new(3, DamageType::Explosive),
+                        explosion: Some(Explosion {
+                            radius: 2.,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    range: 30.,
+                    min_range: Some(4.),
+                    cooldown: 4.,
+                    spread: 0.,
+                    ..Default::default()
+                },
+            ],
+            // Drones pick off weakened enemies
+            policy: attack::TargetingPolicy::LowestHealth,
+            ..Default::default()
+        })
+        // Relays targeting data, letting nearby allies shoot further
+        .with(Auras::new(vec![Aura {
+            radius: 12.,
+            affects: Affects::Allies,
+            effect: StatusEffect {
+                id: "targeting-uplink",
+                kind: EffectKind::Modifier {
+                    stat: Stat::Range,
+                    multiplier: 1.25,
+                },
+                duration: 1.,
+                stacking: Stacking::Refresh,
+            },
+        }]))
+        .current_entity()
+        .unwrap()
+}
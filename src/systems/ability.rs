@@ -1,16 +1,29 @@
-use crate::systems::{health::*, selection::*, ui::*};
-use crate::unit::Unit;
-use bevy::prelude::*;
+use crate::systems::{
+    casting::Casting,
+    death::Dying,
+    faction::{Faction, FactionId, Factions},
+    health::*,
+    selection::*,
+    status_effect::*,
+    summon::{Summon, SummonRequest},
+    time::ControlledTime,
+    ui::*,
+    unit::TargetPosition,
+};
+use bevy::{prelude::*, utils::HashMap};
 use bevy_mod_picking::*;
 
+pub type AbilityId = &'static str;
+
 #[derive(PartialEq, Debug)]
 pub enum Ability {
     Select,
     SwitchCamera,
     SwitchBack,
-    Teleport(Entity),
-    HealUnit,
-    HealArea,
+    /// Waiting for the player to pick a target for a registered ability
+    Cast {
+        ability: AbilityId,
+    },
 }
 
 pub struct CurrentAbility {
@@ -24,81 +37,859 @@ impl Default for CurrentAbility {
     }
 }
 
+/// What the player has to click on to cast an ability
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Targeting {
+    /// Cast right away, without any target
+    None,
+    /// Cast right away on the caster
+    OnSelf,
+    /// Cast on the unit that gets clicked
+    Unit,
+    /// Cast on the point of the ground that gets clicked
+    Point,
+    /// Cast on every unit around the point that gets clicked
+    Area { radius: f32 },
+    /// Cast on every unit in a cone starting at the caster, aimed at the point that gets clicked
+    Cone { length: f32, angle: f32 },
+}
+
+impl Targeting {
+    /// Whether a unit at `position` is inside the area of an ability aimed at `point`
+    pub fn covers(&self, caster: Vec3, point: Vec3, position: Vec3) -> bool {
+        match *self {
+            Targeting::Area { radius } => {
+                let mut difference = position - point;
+                difference.y = 0.;
+                difference.length() <= radius
+            }
+            Targeting::Cone { length, angle } => {
+                let mut aim = point - caster;
+                aim.y = 0.;
+                let mut difference = position - caster;
+                difference.y = 0.;
+                if difference.length() > length {
+                    return false;
+                }
+                // The caster's own position is always covered
+                difference.length() <= f32::EPSILON
+                    || aim.length() <= f32::EPSILON
+                    || aim.angle_between(difference) <= angle * 0.5
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Which units an ability can affect, relative to the caster
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Affects {
+    Allies,
+    Enemies,
+    All,
+}
+
+impl Affects {
+    pub fn includes(&self, factions: &Factions, caster: FactionId, target: FactionId) -> bool {
+        match self {
+            Affects::Allies => factions.is_allied(caster, target),
+            Affects::Enemies => factions.is_hostile(caster, target),
+            Affects::All => true,
+        }
+    }
+}
+
+/// Something that happens when an ability is cast
+#[derive(Clone, Copy, Debug)]
+pub enum AbilityEffect {
+    /// Heals the affected units
+    Heal(i16),
+    /// Damages the affected units
+    Damage(Damage),
+    /// Puts a status effect on the affected units
+    ApplyStatus(StatusEffect),
+    /// Moves the caster to the targeted point
+    TeleportCaster,
+    /// Creates units for the caster at the targeted point
+    Summon(Summon),
+}
+
+/// Describes an ability. Abilities are registered once with `register_ability` and then
+/// referenced by id from `UnitAbilities`
+pub struct AbilityDefinition {
+    pub id: AbilityId,
+    pub name: String,
+    pub targeting: Targeting,
+    pub affects: Affects,
+    pub range: f32,     // Maximum distance between the caster and the target
+    pub cost: f32,      // Energy spent on each cast
+    pub cooldown: f32,  // Seconds before the same unit can cast it again
+    pub cast_time: f32, // Seconds the caster winds up before the effects land
+    pub channel: Option<Channel>,
+    pub autocast: Option<AutocastRule>, // Abilities without a rule can't be autocast
+    pub effects: Vec<AbilityEffect>,
+}
+
+/// How a unit picks the target of an ability it casts on its own
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AutocastRule {
+    /// Unit in range with the lowest health, among the ones the ability affects, once its health
+    /// drops below this fraction of its maximum
+    LowestHealthBelow(f32),
+}
+
+/// Channelled abilities keep applying their effects while the caster stays still
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Channel {
+    pub duration: f32,
+    /// Seconds between each application of the effects. Shorter than `MIN_TICK_INTERVAL` is
+    /// clamped to it
+    pub interval: f32,
+}
+
+#[derive(Default)]
+pub struct AbilityRegistry {
+    abilities: HashMap<AbilityId, AbilityDefinition>,
+}
+
+impl AbilityRegistry {
+    pub fn register(&mut self, mut ability: AbilityDefinition) {
+        // A channel that doesn't wait between ticks would tick forever in a single frame
+        if let Some(channel) = &mut ability.channel {
+            channel.interval = channel.interval.max(MIN_TICK_INTERVAL);
+        }
+        self.abilities.insert(ability.id, ability);
+    }
+
+    pub fn get(&self, id: AbilityId) -> Option<&AbilityDefinition> {
+        self.abilities.get(id)
+    }
+}
+
+/// Lets plugins register their abilities while building the app
+pub trait RegisterAbility {
+    fn register_ability(&mut self, ability: AbilityDefinition) -> &mut Self;
+}
+
+impl RegisterAbility for AppBuilder {
+    fn register_ability(&mut self, ability: AbilityDefinition) -> &mut Self {
+        if let Some(mut registry) = self.resources().get_mut::<AbilityRegistry>() {
+            registry.register(ability);
+            return self;
+        }
+
+        let mut registry = AbilityRegistry::default();
+        registry.register(ability);
+        self.add_resource(registry)
+    }
+}
+
+/// Resource spent by units to cast abilities
+pub struct Energy {
+    pub value: f32,
+    pub max: f32,
+    pub regeneration: f32, // Energy gained per second
+}
+
+impl Energy {
+    pub fn new(max: f32, regeneration: f32) -> Self {
+        Self {
+            value: max,
+            max,
+            regeneration,
+        }
+    }
+}
+
+fn regenerate_energy(time: Res<ControlledTime>, mut query: Query<&mut Energy>) {
+    for mut energy in query.iter_mut() {
+        energy.value = (energy.value + energy.regeneration * time.delta_seconds).min(energy.max);
+    }
+}
+
+/// Whether a unit can cast one of its abilities right now
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CastState {
+    Ready,
+    /// Seconds left before it can be cast again
+    Cooldown(f32),
+    NotEnoughEnergy,
+}
+
 #[derive(Default)]
 pub struct UnitAbilities {
-    pub abilities: Vec<AbilityButton>,
+    pub abilities: Vec<AbilityId>,
+    // When each ability comes off cooldown
+    ready_at: HashMap<AbilityId, f64>,
+    // Abilities that the unit casts on its own
+    autocast: Vec<AbilityId>,
 }
-pub struct AbilityButton {
-    pub name: String,
-    pub id: &'static str,
-    pub callback: AbilityChangeCallback,
+
+impl UnitAbilities {
+    pub fn new(abilities: Vec<AbilityId>) -> Self {
+        Self {
+            abilities,
+            ready_at: HashMap::default(),
+            autocast: Vec::new(),
+        }
+    }
+
+    pub fn is_autocast(&self, id: AbilityId) -> bool {
+        self.autocast.contains(&id)
+    }
+
+    pub fn set_autocast(&mut self, id: AbilityId, enabled: bool) {
+        self.autocast.retain(|autocast| *autocast != id);
+        if enabled && self.abilities.contains(&id) {
+            self.autocast.push(id);
+        }
+    }
+
+    pub fn cast_state(
+        &self,
+        definition: &AbilityDefinition,
+        energy: Option<&Energy>,
+        current_time: f64,
+    ) -> CastState {
+        let ready_at = self.ready_at.get(definition.id).copied().unwrap_or(0.);
+        if ready_at > current_time {
+            CastState::Cooldown((ready_at - current_time) as f32)
+        } else if definition.cost > energy.map_or(0., |energy| energy.value) {
+            CastState::NotEnoughEnergy
+        } else {
+            CastState::Ready
+        }
+    }
+
+    /// Pays for casting the ability. Returns false if it can't be cast right now
+    pub fn start_cast(
+        &mut self,
+        definition: &AbilityDefinition,
+        energy: Option<&mut Energy>,
+        current_time: f64,
+    ) -> bool {
+        if self.cast_state(definition, energy.as_deref(), current_time) != CastState::Ready {
+            return false;
+        }
+
+        if let Some(energy) = energy {
+            energy.value -= definition.cost;
+        }
+        self.ready_at
+            .insert(definition.id, current_time + definition.cooldown as f64);
+        true
+    }
+}
+
+/// Where an ability was cast
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CastTarget {
+    None,
+    Unit(Entity),
+    Point(Vec3),
+}
+
+/// Cast ordered on a target that was out of range. The caster walks towards the target and
+/// casts once it's close enough. Giving the unit a new order cancels it
+pub struct PendingCast {
+    pub ability: AbilityId,
+    pub target: CastTarget,
+}
+
+/// Sent when a unit casts an ability. The cast is paid for, then winds up or gets channelled
+pub struct AbilityCastEvent {
+    pub ability: AbilityId,
+    pub caster: Entity,
+    pub target: CastTarget,
+}
+
+/// Sent when the effects of an ability land: once the cast is over, and on every tick of a
+/// channelled ability
+pub struct AbilityResolveEvent {
+    pub ability: AbilityId,
+    pub caster: Entity,
+    pub target: CastTarget,
+}
+
+#[derive(Default)]
+struct State {
+    ability_resolve_event_reader: EventReader<AbilityResolveEvent>,
+}
+
+/// Abilities currently shown on the command card, in the order of their buttons
+#[derive(Default)]
+struct CommandCard {
+    abilities: Vec<AbilityId>,
+}
+
+fn command_card_button_id(id: AbilityId) -> ButtonIdentifier {
+    format!("ability-{}", id)
 }
 
-fn add_ability_buttons_for_selected_units(
+/// Shows one button for each ability of the selected units, however many of them have it
+fn update_command_card(
     mut buttons: ResMut<AvailableButtons>,
-    query: Query<(&Selectable, &UnitAbilities, Entity), Mutated<Selectable>>,
+    mut command_card: ResMut<CommandCard>,
+    registry: Res<AbilityRegistry>,
+    changed_query: Query<&Selectable, Changed<Selectable>>,
+    query: Query<(&Selectable, &UnitAbilities)>,
 ) {
-    for (selectable, abilities, entity) in &mut query.iter() {
-        if selectable.selected && !selectable.previously_selected {
-            for ability in &abilities.abilities {
-                let _ = buttons.add_button((
-                    ability.name.clone(),
-                    format!("{}-{:?}", ability.id, entity),
-                    ability.callback,
-                    CallbackData {
-                        entity: Some(entity),
-                        associated_circle: Some(selectable.circle),
-                    },
-                ));
+    if changed_query.iter().next().is_none() {
+        return;
+    }
+
+    let mut abilities = Vec::new();
+    for (selectable, unit_abilities) in query.iter() {
+        if !selectable.selected {
+            continue;
+        }
+
+        for id in &unit_abilities.abilities {
+            if !abilities.contains(id) && registry.get(*id).is_some() {
+                abilities.push(*id);
             }
-        } else if !selectable.selected {
-            for ability in &abilities.abilities {
-                let _ = buttons.remove_button(format!("{}-{:?}", ability.id, entity));
+        }
+    }
+
+    if abilities == command_card.abilities {
+        return;
+    }
+
+    for id in &command_card.abilities {
+        buttons.remove_button(command_card_button_id(*id));
+    }
+    for &id in &abilities {
+        let definition = registry.get(id).unwrap();
+        // Abilities without a target are cast right away, the others wait for a click
+        let callback = match definition.targeting {
+            Targeting::None | Targeting::OnSelf => {
+                button_callback(move |world, resources, _| cast_instantly(world, resources, id))
             }
+            _ => button_callback(move |_, resources, _| {
+                resources.get_mut::<CurrentAbility>().unwrap().ability =
+                    Ability::Cast { ability: id };
+            }),
+        };
+        // Right clicking toggles autocast, for the abilities that support it
+        let secondary_callback = definition
+            .autocast
+            .map(|_| button_callback(move |world, _, _| toggle_autocast(world, id)));
+        let _ = buttons.add_button((
+            definition.name.clone(),
+            command_card_button_id(id),
+            callback,
+            secondary_callback,
+            CallbackData { ability: Some(id) },
+        ));
+    }
+    command_card.abilities = abilities;
+}
+
+/// Casts an ability that doesn't need a target with the best selected caster, or with every
+/// selected caster while control is held
+fn cast_instantly(world: &mut World, resources: &mut Resources, id: AbilityId) {
+    let registry = resources.get::<AbilityRegistry>().unwrap();
+    let definition = match registry.get(id) {
+        Some(definition) => definition,
+        None => return,
+    };
+    let time = resources.get::<ControlledTime>().unwrap();
+    let keyboard_input = resources.get::<Input<KeyCode>>().unwrap();
+    let mut cast_events = resources.get_mut::<Events<AbilityCastEvent>>().unwrap();
+
+    let casters = ready_casters(
+        definition,
+        time.seconds_since_startup,
+        world.query_filtered::<(
+            &Selectable,
+            &Transform,
+            &Faction,
+            &UnitAbilities,
+            Option<&Energy>,
+            Entity,
+        ), Without<Dying>>(),
+    );
+    let chosen: Vec<&Caster> = if keyboard_input.pressed(KeyCode::LControl) {
+        casters.iter().collect()
+    } else {
+        best_caster(&casters, None).into_iter().collect()
+    };
+
+    for caster in chosen {
+        cast_events.send(AbilityCastEvent {
+            ability: id,
+            caster: caster.entity,
+            target: match definition.targeting {
+                Targeting::OnSelf => CastTarget::Unit(caster.entity),
+                _ => CastTarget::None,
+            },
+        });
+    }
+}
+
+/// Turns autocast on for every selected unit with the ability, or off if they all have it on
+fn toggle_autocast(world: &mut World, id: AbilityId) {
+    let selected: Vec<(Entity, bool)> = world
+        .query::<(&Selectable, &UnitAbilities, Entity)>()
+        .filter(|(selectable, abilities, _)| {
+            selectable.selected && abilities.abilities.contains(&id)
+        })
+        .map(|(_, abilities, entity)| (entity, abilities.is_autocast(id)))
+        .collect();
+    let enabled = selected.iter().any(|(_, autocast)| !autocast);
+
+    for (entity, _) in selected {
+        if let Ok(mut abilities) = world.get_mut::<UnitAbilities>(entity) {
+            abilities.set_autocast(id, enabled);
         }
     }
 }
 
-fn teleport_ability(
+/// A selected unit that is ready to cast the ability being targeted
+pub struct Caster {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub faction: FactionId,
+    energy: f32,
+}
+
+/// Selected units that can cast the ability right now
+pub fn ready_casters<'a>(
+    definition: &AbilityDefinition,
+    current_time: f64,
+    units: impl Iterator<
+        Item = (
+            &'a Selectable,
+            &'a Transform,
+            &'a Faction,
+            &'a UnitAbilities,
+            Option<&'a Energy>,
+            Entity,
+        ),
+    >,
+) -> Vec<Caster> {
+    units
+        .filter(|(selectable, _, _, abilities, energy, _)| {
+            selectable.selected
+                && abilities.abilities.contains(&definition.id)
+                && abilities.cast_state(definition, *energy, current_time) == CastState::Ready
+        })
+        .map(|(_, transform, faction, _, energy, entity)| Caster {
+            entity,
+            position: transform.translation,
+            faction: faction.faction,
+            energy: energy.map_or(0., |energy| energy.value),
+        })
+        .collect()
+}
+
+/// The caster closest to `target`, or the one with the most energy left when there's a tie
+pub fn best_caster(casters: &[Caster], target: Option<Vec3>) -> Option<&Caster> {
+    let distance =
+        |caster: &Caster| target.map_or(0., |target| (target - caster.position).length());
+    casters.iter().min_by(|a, b| {
+        distance(a)
+            .partial_cmp(&distance(b))
+            .unwrap()
+            .then(b.energy.partial_cmp(&a.energy).unwrap())
+    })
+}
+
+/// Combined state of an ability over several casters: ready if any of them can cast it
+pub fn group_cast_state(states: impl Iterator<Item = CastState>) -> Option<CastState> {
+    states.fold(None, |group, state| match (group, state) {
+        (Some(CastState::Ready), _) | (_, CastState::Ready) => Some(CastState::Ready),
+        (Some(CastState::Cooldown(a)), CastState::Cooldown(b)) => {
+            Some(CastState::Cooldown(a.min(b)))
+        }
+        (Some(CastState::Cooldown(a)), CastState::NotEnoughEnergy) => Some(CastState::Cooldown(a)),
+        (_, state) => Some(state),
+    })
+}
+
+/// Picks the target of the ability being cast, and casts it once there is a valid one.
+/// The best selected caster casts it, or every selected caster while control is held
+#[allow(clippy::too_many_arguments)]
+fn target_abilities(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
     pick_state: Res<PickState>,
+    keyboard_input: Res<Input<KeyCode>>,
     mouse_button_inputs: Res<Input<MouseButton>>,
+    registry: Res<AbilityRegistry>,
+    factions: Res<Factions>,
     mut ability: ResMut<CurrentAbility>,
-    mut query: Query<(&mut Transform, &Unit)>,
+    mut cast_events: ResMut<Events<AbilityCastEvent>>,
+    caster_query: Query<
+        (
+            &Selectable,
+            &Transform,
+            &Faction,
+            &UnitAbilities,
+            Option<&Energy>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    pending_query: Query<&PendingCast>,
+    target_query: Query<(&Health, &Faction, &Transform), Without<Dying>>,
 ) {
-    if let Ability::Teleport(entity) = ability.ability {
-        if mouse_button_inputs.just_pressed(MouseButton::Right) {
-            // Get the world position
-            if let Some((_top_entity, intersection)) = pick_state.top(Group::default()) {
-                let mut pos = *intersection.position();
-                pos.y = 1.;
-
-                if let Ok(mut transform) = query.get_component_mut::<Transform>(entity) {
-                    transform.translation = pos;
+    let id = match ability.ability {
+        Ability::Cast { ability } => ability,
+        _ => return,
+    };
+
+    let definition = match registry.get(id) {
+        Some(definition) => definition,
+        None => {
+            ability.ability = Ability::Select;
+            return;
+        }
+    };
+
+    // Stop targeting once no selected unit can cast it, e.g. they died or are on cooldown
+    let casters = ready_casters(definition, time.seconds_since_startup, caster_query.iter());
+    if casters.is_empty() {
+        ability.ability = Ability::Select;
+        return;
+    }
+
+    // Right clicking cancels the ability
+    if mouse_button_inputs.just_pressed(MouseButton::Right) {
+        ability.ability = Ability::Select;
+        return;
+    }
+
+    // Where the player clicked, if the ability needs a click
+    let clicked = match definition.targeting {
+        Targeting::None | Targeting::OnSelf => None,
+        Targeting::Unit | Targeting::Point | Targeting::Area { .. } | Targeting::Cone { .. } => {
+            if !mouse_button_inputs.just_pressed(MouseButton::Left) {
+                return;
+            }
+
+            match pick_state.top(Group::default()) {
+                Some((top_entity, intersection)) => Some(match definition.targeting {
+                    Targeting::Unit => match target_query.get(*top_entity) {
+                        Ok(_) => CastTarget::Unit(*top_entity),
+                        Err(_) => return,
+                    },
+                    _ => CastTarget::Point(*intersection.position()),
+                }),
+                None => return,
+            }
+        }
+    };
+
+    let clicked_position = match clicked {
+        Some(CastTarget::Unit(entity)) => target_query
+            .get_component::<Transform>(entity)
+            .map(|transform| transform.translation)
+            .ok(),
+        Some(CastTarget::Point(point)) => Some(point),
+        _ => None,
+    };
+    let chosen: Vec<&Caster> = if keyboard_input.pressed(KeyCode::LControl) {
+        casters.iter().collect()
+    } else {
+        best_caster(&casters, clicked_position)
+            .into_iter()
+            .collect()
+    };
+
+    for caster in chosen {
+        let target = match clicked {
+            Some(target) => target,
+            None if definition.targeting == Targeting::OnSelf => CastTarget::Unit(caster.entity),
+            None => CastTarget::None,
+        };
+
+        // Units that the caster isn't allowed to affect are skipped
+        if let CastTarget::Unit(entity) = target {
+            match target_query.get_component::<Faction>(entity) {
+                Ok(faction)
+                    if definition
+                        .affects
+                        .includes(&factions, caster.faction, faction.faction) => {}
+                _ => continue,
+            }
+        }
+
+        let target_position = match definition.targeting {
+            // Cones start from the caster, so the click only gives the direction
+            Targeting::Cone { .. } => None,
+            _ => clicked_position,
+        };
+        match target_position {
+            // Walk into range first if the target is too far away
+            Some(position) if !in_cast_range(caster.position, position, definition) => {
+                commands.insert_one(
+                    caster.entity,
+                    PendingCast {
+                        ability: id,
+                        target,
+                    },
+                );
+            }
+            _ => {
+                // Casting something else cancels the cast the unit was walking to
+                if pending_query.get(caster.entity).is_ok() {
+                    commands.remove_one::<PendingCast>(caster.entity);
                 }
+                cast_events.send(AbilityCastEvent {
+                    ability: id,
+                    caster: caster.entity,
+                    target,
+                });
+            }
+        }
+    }
+    ability.ability = Ability::Select;
+}
+
+pub fn in_cast_range(caster: Vec3, target: Vec3, definition: &AbilityDefinition) -> bool {
+    let mut difference = target - caster;
+    difference.y = 0.;
+    difference.length() <= definition.range
+}
+
+/// Moves casters with a pending cast towards their target, and casts once they're in range
+fn move_to_cast(
+    commands: &mut Commands,
+    registry: Res<AbilityRegistry>,
+    mut cast_events: ResMut<Events<AbilityCastEvent>>,
+    mut caster_query: Query<
+        (&PendingCast, &Transform, &mut TargetPosition, Entity),
+        Without<Dying>,
+    >,
+    target_query: Query<&Transform, Without<Dying>>,
+) {
+    for (pending, transform, mut target_position, entity) in caster_query.iter_mut() {
+        let definition = registry.get(pending.ability);
+        let position = match pending.target {
+            CastTarget::Unit(target) => target_query
+                .get(target)
+                .map(|transform| transform.translation)
+                .ok(),
+            CastTarget::Point(point) => Some(point),
+            CastTarget::None => Some(transform.translation),
+        };
+
+        let (definition, position) = match (definition, position) {
+            (Some(definition), Some(position)) => (definition, position),
+            // The target died on the way
+            _ => {
+                commands.remove_one::<PendingCast>(entity);
+                continue;
             }
+        };
 
-            ability.ability = Ability::Select;
+        if in_cast_range(transform.translation, position, definition) {
+            target_position.pos = None;
+            cast_events.send(AbilityCastEvent {
+                ability: pending.ability,
+                caster: entity,
+                target: pending.target,
+            });
+            commands.remove_one::<PendingCast>(entity);
+        } else {
+            target_position.update_to_vec(&position);
         }
     }
 }
 
-fn heal_unit_ability(
-    pick_state: Res<PickState>,
-    mouse_button_inputs: Res<Input<MouseButton>>,
-    mut ability: ResMut<CurrentAbility>,
-    mut query: Query<(&mut Health, &Unit)>,
+/// Casts the abilities that units have on autocast, on the targets picked by their rule.
+/// Units that are busy casting or walking to cast something are left alone
+#[allow(clippy::too_many_arguments)]
+fn autocast_abilities(
+    time: Res<ControlledTime>,
+    registry: Res<AbilityRegistry>,
+    factions: Res<Factions>,
+    mut cast_events: ResMut<Events<AbilityCastEvent>>,
+    caster_query: Query<
+        (
+            &UnitAbilities,
+            Option<&Energy>,
+            &Transform,
+            &Faction,
+            Option<&PendingCast>,
+            Option<&Casting>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    target_query: Query<(&Health, &Transform, &Faction, Entity), Without<Dying>>,
 ) {
-    if Ability::HealUnit != ability.ability {
-        return;
+    for (abilities, energy, transform, faction, pending, casting, caster) in caster_query.iter() {
+        if pending.is_some() || casting.is_some() {
+            continue;
+        }
+
+        for &id in &abilities.autocast {
+            let definition = match registry.get(id) {
+                Some(definition) => definition,
+                None => continue,
+            };
+            let rule = match definition.autocast {
+                Some(rule) => rule,
+                None => continue,
+            };
+            if abilities.cast_state(definition, energy, time.seconds_since_startup)
+                != CastState::Ready
+            {
+                continue;
+            }
+
+            let candidates =
+                target_query
+                    .iter()
+                    .filter(|(_, target_transform, target_faction, target)| {
+                        if definition.targeting == Targeting::OnSelf {
+                            return *target == caster;
+                        }
+
+                        definition.affects.includes(
+                            &factions,
+                            faction.faction,
+                            target_faction.faction,
+                        ) && in_cast_range(
+                            transform.translation,
+                            target_transform.translation,
+                            definition,
+                        )
+                    });
+            let chosen = match rule {
+                AutocastRule::LowestHealthBelow(threshold) => candidates
+                    .map(|(health, target_transform, _, target)| {
+                        let fraction = health.value as f32 / health.max_health() as f32;
+                        (fraction, target_transform.translation, target)
+                    })
+                    .filter(|(fraction, _, _)| *fraction < threshold)
+                    .min_by(|(a, _, _), (b, _, _)| a.partial_cmp(b).unwrap()),
+            };
+
+            if let Some((_, position, target)) = chosen {
+                cast_events.send(AbilityCastEvent {
+                    ability: id,
+                    caster,
+                    target: match definition.targeting {
+                        Targeting::None => CastTarget::None,
+                        Targeting::OnSelf | Targeting::Unit => CastTarget::Unit(target),
+                        Targeting::Point | Targeting::Area { .. } | Targeting::Cone { .. } => {
+                            CastTarget::Point(position)
+                        }
+                    },
+                });
+                // One cast at a time, the next ability waits for the next frame
+                break;
+            }
+        }
     }
+}
+
+/// Applies the effects of every ability that resolved
+#[allow(clippy::too_many_arguments)]
+fn apply_ability_effects(
+    mut state: ResMut<State>,
+    registry: Res<AbilityRegistry>,
+    factions: Res<Factions>,
+    resolve_events: Res<Events<AbilityResolveEvent>>,
+    mut damage_requests: ResMut<Events<DamageRequest>>,
+    mut heal_requests: ResMut<Events<HealRequest>>,
+    mut status_effect_requests: ResMut<Events<StatusEffectRequest>>,
+    mut summon_requests: ResMut<Events<SummonRequest>>,
+    mut query: Query<(&mut Transform, &Faction, &Health, Entity), Without<Dying>>,
+) {
+    for event in state.ability_resolve_event_reader.iter(&resolve_events) {
+        let definition = match registry.get(event.ability) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let (caster_position, caster_faction) = match (
+            query.get_component::<Transform>(event.caster),
+            query.get_component::<Faction>(event.caster),
+        ) {
+            (Ok(transform), Ok(faction)) => (transform.translation, faction.faction),
+            _ => continue,
+        };
 
-    if mouse_button_inputs.just_pressed(MouseButton::Left) {
-        // Get the world position
-        if let Some((top_entity, _intersection)) = pick_state.top(Group::default()) {
-            if let Ok(mut health) = query.get_component_mut::<Health>(*top_entity) {
-                health.heal(20);
+        let affected: Vec<Entity> = match (definition.targeting, event.target) {
+            (Targeting::Area { .. }, CastTarget::Point(point))
+            | (Targeting::Cone { .. }, CastTarget::Point(point)) => query
+                .iter()
+                .filter(|(transform, faction, _, _)| {
+                    definition
+                        .targeting
+                        .covers(caster_position, point, transform.translation)
+                        && definition
+                            .affects
+                            .includes(&factions, caster_faction, faction.faction)
+                })
+                .map(|(_, _, _, entity)| entity)
+                .collect(),
+            (_, CastTarget::Unit(entity)) => vec![entity],
+            _ => Vec::new(),
+        };
+
+        for effect in &definition.effects {
+            match effect {
+                AbilityEffect::Heal(amount) => {
+                    for target in &affected {
+                        heal_requests.send(HealRequest {
+                            source: Some(event.caster),
+                            target: *target,
+                            amount: *amount,
+                            cause: Cause::Ability,
+                        });
+                    }
+                }
+                AbilityEffect::Damage(damage) => {
+                    for target in &affected {
+                        damage_requests.send(DamageRequest {
+                            source: Some(event.caster),
+                            target: *target,
+                            damage: *damage,
+                            cause: Cause::Ability,
+                            on_hit: None,
+                        });
+                    }
+                }
+                AbilityEffect::ApplyStatus(status_effect) => {
+                    for target in &affected {
+                        status_effect_requests.send(StatusEffectRequest {
+                            source: Some(event.caster),
+                            target: *target,
+                            effect: *status_effect,
+                        });
+                    }
+                }
+                AbilityEffect::TeleportCaster => {
+                    if let CastTarget::Point(mut point) = event.target {
+                        if let Ok(mut transform) =
+                            query.get_component_mut::<Transform>(event.caster)
+                        {
+                            point.y = transform.translation.y;
+                            transform.translation = point;
+                        }
+                    }
+                }
+                AbilityEffect::Summon(summon) => {
+                    let position = match event.target {
+                        CastTarget::Point(point) => point,
+                        CastTarget::Unit(target) => query
+                            .get_component::<Transform>(target)
+                            .map(|transform| transform.translation)
+                            .unwrap_or(caster_position),
+                        CastTarget::None => caster_position,
+                    };
+                    summon_requests.send(SummonRequest {
+                        summoner: event.caster,
+                        ability: event.ability,
+                        summon: *summon,
+                        position,
+                    });
+                }
             }
-            ability.ability = Ability::Select;
         }
     }
 }
@@ -107,9 +898,17 @@ pub struct AbilityPlugin;
 impl Plugin for AbilityPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<CurrentAbility>()
-            .add_system(add_ability_buttons_for_selected_units.system())
-            .add_system(teleport_ability.system())
-            .add_system(heal_unit_ability.system());
+            .init_resource::<AbilityRegistry>()
+            .init_resource::<State>()
+            .add_event::<AbilityCastEvent>()
+            .add_event::<AbilityResolveEvent>()
+            .init_resource::<CommandCard>()
+            .add_system(update_command_card.system())
+            .add_system(regenerate_energy.system())
+            .add_system(target_abilities.system())
+            .add_system(move_to_cast.system())
+            .add_system(autocast_abilities.system())
+            .add_system(apply_ability_effects.system());
     }
 }
 
@@ -120,9 +919,7 @@ impl fmt::Display for Ability {
             Ability::Select => write!(f, "Select"),
             Ability::SwitchCamera => write!(f, "Switch Camera"),
             Ability::SwitchBack => write!(f, "Switch Back"),
-            Ability::Teleport(_) => write!(f, "Teleport"),
-            Ability::HealUnit => write!(f, "Heal unit"),
-            Ability::HealArea => write!(f, "Heal area"),
+            Ability::Cast { ability } => write!(f, "{}", ability),
         }
     }
 }
@@ -1,9 +1,14 @@
 use crate::{
     bundles::*,
-    systems::{attack, faction::*, time::*, unit::*},
+    systems::{
+        attack, death::*, faction::*, health::Resistances, status_effect::*, time::*, unit::*,
+    },
 };
 use bevy::{math::Vec3, prelude::*};
 use bevy_contrib_colors::Tailwind;
+use rand::Rng;
+
+const SWARMER_PROBABILITY: f64 = 0.4;
 
 struct SpawnTimer(Timer);
 fn create_random_aliens(
@@ -20,27 +25,75 @@ fn create_random_aliens(
             1.0,
             50. * time.seconds_since_startup.to_degrees().cos() as f32,
         );
+        // Some aliens are swarmers that chase down the player's units instead of shooting
+        let is_swarmer = rand::thread_rng().gen_bool(SWARMER_PROBABILITY);
+
         commands
             .spawn(PbrBundle {
                 mesh: resource.mesh.clone(),
-                material: resource.material.clone(),
+                material: if is_swarmer {
+                    resource.swarmer_material.clone()
+                } else {
+                    resource.material.clone()
+                },
                 transform: Transform::from_translation(position),
                 ..Default::default()
             })
             .with_bundle(UnitBundle {
+                unit: Unit {
+                    speed: if is_swarmer { 10.0 } else { 7.0 },
+                    ..Default::default()
+                },
                 target_position: TargetPosition {
                     pos: Some(Vec3::zero()),
                 },
-                faction: Faction::new(Factions::Aliens),
+                faction: Faction::new(FactionId::ALIENS),
+                class: if is_swarmer {
+                    UnitClass::Swarmer
+                } else {
+                    UnitClass::Infantry
+                },
                 ..UnitBundle::default()
             })
-            .with(attack::Ranged::default());
+            // Alien carapaces deflect energy weapons
+            .with(Resistances {
+                energy: 0.5,
+                ..Default::default()
+            })
+            .with(DeathAnimation {
+                style: DeathStyle::Debris,
+                duration: 1.,
+            });
+
+        if is_swarmer {
+            commands.with(attack::Melee {
+                aggro_range: 60.,
+                // Bites cripple, slowing the target down
+                on_hit: Some(StatusEffect {
+                    id: "crippled",
+                    kind: EffectKind::Slow { multiplier: 0.8 },
+                    duration: 3.,
+                    stacking: Stacking::Stack { max_stacks: 3 },
+                }),
+                ..Default::default()
+            });
+        } else {
+            // Aliens are sloppy shooters
+            commands.with(attack::Ranged {
+                weapons: vec![attack::Weapon {
+                    spread: 0.1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            });
+        }
     }
 }
 
 struct AlienMeshResource {
     mesh: Handle<Mesh>,
     material: Handle<StandardMaterial>,
+    swarmer_material: Handle<StandardMaterial>,
 }
 
 impl FromResources for AlienMeshResource {
@@ -50,6 +103,7 @@ impl FromResources for AlienMeshResource {
         AlienMeshResource {
             mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
             material: materials.add(Tailwind::PURPLE400.into()),
+            swarmer_material: materials.add(Tailwind::PURPLE700.into()),
         }
     }
 }
@@ -1,89 +1,565 @@
-use crate::systems::{bullet::*, faction::*, time::*, unit::*};
-use bevy::{math::Vec3, prelude::*};
+use crate::helpers::ballistics::*;
+use crate::systems::{
+    beam::*, bullet::*, death::Dying, drone::Drone, faction::*, health::*, line_of_sight::*,
+    status_effect::*, time::*, timed_visual::TimedVisualPool, unit::*,
+};
+use bevy::{math::Vec3, prelude::*, utils::HashMap};
 
-pub struct Ranged {
-    pub range: f32,        // Range that the enemy needs to be in before it fires
-    pub attack_speed: u16, // Number of attacks per second
+/// How a ranged unit picks which enemy in range to shoot at
+#[derive(Clone, Copy, PartialEq)]
+pub enum TargetingPolicy {
+    Closest,
+    LowestHealth,
+    /// Enemy that deals the most damage per second
+    HighestThreat,
+    /// Keep shooting the current target until it dies or leaves range
+    KeepCurrent,
+    /// Closest enemy of the given class, or the closest one if there's none in range
+    PreferClass(UnitClass),
+}
+impl Default for TargetingPolicy {
+    fn default() -> Self {
+        TargetingPolicy::Closest
+    }
+}
+
+/// What a ranged unit shoots with
+#[derive(Clone, Copy)]
+pub enum RangedWeapon {
+    Projectile(Projectile),
+    Beam(Beam),
+}
+impl Default for RangedWeapon {
+    fn default() -> Self {
+        RangedWeapon::Projectile(Projectile::default())
+    }
+}
+
+impl RangedWeapon {
+    pub fn damage(&self) -> Damage {
+        match self {
+            RangedWeapon::Projectile(projectile) => projectile.damage,
+            RangedWeapon::Beam(beam) => beam.damage,
+        }
+    }
+
+    /// Arcing projectiles are fired over whatever is in the way
+    fn is_indirect(&self) -> bool {
+        matches!(
+            self,
+            RangedWeapon::Projectile(Projectile {
+                trajectory: Trajectory::Ballistic { .. },
+                ..
+            })
+        )
+    }
+}
+
+/// One of the weapons carried by a ranged unit. Each one fires on its own cooldown
+#[derive(Clone, Copy)]
+pub struct Weapon {
+    pub kind: RangedWeapon,
+    pub range: f32,             // Range that the enemy needs to be in before it fires
+    pub min_range: Option<f32>, // Enemies closer than this can't be targeted
+    pub cooldown: f32,          // Seconds between shots
+    pub spread: f32, // Maximum angle in radians between where the unit aims and where it shoots
     pub last_attack: f64,
 }
-impl Default for Ranged {
+impl Default for Weapon {
     fn default() -> Self {
         Self {
+            kind: RangedWeapon::default(),
             range: 20.,
-            attack_speed: 1,
+            min_range: None,
+            cooldown: 1.,
+            spread: 0.02,
             last_attack: 0.,
         }
     }
 }
 
-impl Ranged {
+impl Weapon {
+    /// The weapon as it is with the unit's buffs and debuffs
+    fn modified(mut self, effects: Option<&StatusEffects>) -> Self {
+        if let Some(effects) = effects {
+            self.range *= effects.stat_multiplier(Stat::Range);
+            self.cooldown /= effects.stat_multiplier(Stat::AttackRate);
+        }
+        self
+    }
+
     fn can_shoot(&self, current_time: f64) -> bool {
-        self.last_attack + (1. / self.attack_speed as f64) < current_time
+        self.last_attack + (self.cooldown as f64) < current_time
+    }
+
+    fn in_range(&self, distance: f32) -> bool {
+        distance < self.range
+            && self
+                .min_range
+                .map_or(true, |min_range| distance >= min_range)
     }
 }
 
+pub struct Ranged {
+    pub weapons: Vec<Weapon>,
+    pub policy: TargetingPolicy,
+    pub current_target: Option<Entity>,
+    // Target ordered by the player, which overrides the policy
+    pub focus_target: Option<Entity>,
+}
+impl Default for Ranged {
+    fn default() -> Self {
+        Self {
+            weapons: vec![Weapon::default()],
+            policy: TargetingPolicy::default(),
+            current_target: None,
+            focus_target: None,
+        }
+    }
+}
+
+impl Ranged {
+    /// Range of the longest reaching weapon
+    pub fn range(&self) -> f32 {
+        self.weapons
+            .iter()
+            .map(|weapon| weapon.range)
+            .fold(0., f32::max)
+    }
+
+    /// Damage per second dealt by this unit
+    fn threat(&self) -> f32 {
+        self.weapons
+            .iter()
+            .map(|weapon| weapon.kind.damage().amount as f32 / weapon.cooldown)
+            .sum()
+    }
+
+    fn choose_target<'a>(
+        &self,
+        weapon: &Weapon,
+        translation: Vec3,
+        faction: FactionId,
+        factions: &Factions,
+        candidates: &'a [TargetCandidate],
+        is_visible: &dyn Fn(&TargetCandidate) -> bool,
+    ) -> Option<&'a TargetCandidate> {
+        let in_range: Vec<&TargetCandidate> = candidates
+            .iter()
+            .filter(|candidate| {
+                factions.is_hostile(faction, candidate.faction)
+                    && weapon.in_range(candidate.distance(translation))
+                    && is_visible(candidate)
+            })
+            .collect();
+
+        if let Some(focus) = self.focus_target {
+            if let Some(candidate) = in_range.iter().find(|candidate| candidate.entity == focus) {
+                return Some(*candidate);
+            }
+        }
+
+        let closest = |candidates: &[&'a TargetCandidate]| {
+            candidates.iter().copied().min_by(|a, b| {
+                a.distance(translation)
+                    .partial_cmp(&b.distance(translation))
+                    .unwrap()
+            })
+        };
+
+        match self.policy {
+            TargetingPolicy::Closest => closest(&in_range),
+            TargetingPolicy::LowestHealth => in_range.iter().copied().min_by(|a, b| {
+                a.health.cmp(&b.health).then(
+                    a.distance(translation)
+                        .partial_cmp(&b.distance(translation))
+                        .unwrap(),
+                )
+            }),
+            TargetingPolicy::HighestThreat => in_range.iter().copied().max_by(|a, b| {
+                a.threat.partial_cmp(&b.threat).unwrap().then(
+                    b.distance(translation)
+                        .partial_cmp(&a.distance(translation))
+                        .unwrap(),
+                )
+            }),
+            TargetingPolicy::KeepCurrent => in_range
+                .iter()
+                .copied()
+                .find(|candidate| Some(candidate.entity) == self.current_target)
+                .or_else(|| closest(&in_range)),
+            TargetingPolicy::PreferClass(class) => {
+                let of_class: Vec<&TargetCandidate> = in_range
+                    .iter()
+                    .copied()
+                    .filter(|candidate| candidate.class == class)
+                    .collect();
+                closest(&of_class).or_else(|| closest(&in_range))
+            }
+        }
+    }
+}
+
+struct TargetCandidate {
+    entity: Entity,
+    position: Vec3,
+    velocity: Vec3,
+    faction: FactionId,
+    health: i16,
+    threat: f32,
+    class: UnitClass,
+    size: f32,
+}
+impl TargetCandidate {
+    fn distance(&self, translation: Vec3) -> f32 {
+        (self.position - translation).length()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn shoot_against_enemies(
-    commands: &mut Commands,
     time: Res<ControlledTime>,
-    bullet_resource: Res<BulletMeshResource>,
-    mut ranged_query: Query<(&Unit, &mut Ranged, &Transform, &Faction)>,
-    // This other query is so we also get all the units that aren't ranged
-    others_query: Query<(&Unit, &Transform, &Faction)>,
+    mut bullet_pool: ResMut<BulletPool>,
+    mut beam_visuals: ResMut<TimedVisualPool<BeamVisual>>,
+    mut damage_requests: ResMut<Events<DamageRequest>>,
+    factions: Res<Factions>,
+    line_of_sight_settings: Res<LineOfSightSettings>,
+    mut ranged_query: Query<
+        (
+            &Unit,
+            &mut Ranged,
+            &mut TargetPosition,
+            &Transform,
+            &Faction,
+            Option<&StatusEffects>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    // Every unit that can be targeted, ranged or not
+    others_query: Query<
+        (
+            &Unit,
+            &Transform,
+            &Faction,
+            &Health,
+            &UnitClass,
+            &UnitSize,
+            &Velocity,
+            Option<&Melee>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    blocker_query: Query<(&LineOfSightBlocker, &Transform, Entity)>,
 ) {
-    let mut unit_positions = Vec::new();
-    for (_, _, transform, faction) in ranged_query.iter_mut() {
-        unit_positions.push((transform.translation, faction.faction));
+    let mut ranged_threats = HashMap::default();
+    for (_, ranged, _, _, _, _, entity) in ranged_query.iter_mut() {
+        ranged_threats.insert(entity, ranged.threat());
+    }
+
+    let mut candidates = Vec::new();
+    let mut obstructions = Vec::new();
+    for (_, transform, faction, health, class, size, velocity, melee, entity) in others_query.iter()
+    {
+        let threat = match (ranged_threats.get(&entity), melee) {
+            (Some(threat), _) => *threat,
+            (None, Some(melee)) => melee.threat(),
+            (None, None) => 0.,
+        };
+
+        candidates.push(TargetCandidate {
+            entity,
+            position: transform.translation,
+            velocity: velocity.velocity,
+            faction: faction.faction,
+            health: health.value,
+            threat,
+            class: *class,
+            size: size.0,
+        });
+        obstructions.push(Obstruction {
+            entity,
+            position: transform.translation,
+            radius: size.0 * 0.5,
+            faction: Some(faction.faction),
+        });
     }
-    for (_, transform, faction) in others_query.iter() {
-        unit_positions.push((transform.translation, faction.faction));
+    for (blocker, transform, entity) in blocker_query.iter() {
+        obstructions.push(Obstruction {
+            entity,
+            position: transform.translation,
+            radius: blocker.radius,
+            faction: None,
+        });
     }
 
-    for (_, mut ranged, transform, faction) in ranged_query.iter_mut() {
+    let mut rng = rand::thread_rng();
+    for (_, mut ranged, mut target, transform, faction, effects, entity) in ranged_query.iter_mut()
+    {
+        // Stunned units can't do anything
+        if effects.map_or(false, |effects| effects.is_stunned()) {
+            continue;
+        }
+
         let translation = transform.translation;
-        if ranged.can_shoot(time.seconds_since_startup) {
-            // Get the closest enemy
-            let mut enemy: Option<(Vec3, f32)> = None; // Option with (difference_vector, difference_distance)
-            for (enemy_transform, enemy_faction) in &unit_positions {
-                // Skip units in same faction
-                if *enemy_faction == faction.faction {
-                    continue;
-                }
+        let range =
+            ranged.range() * effects.map_or(1., |effects| effects.stat_multiplier(Stat::Range));
 
-                let difference = translation - *enemy_transform;
-                let difference_distance = difference.length();
-
-                // If it's in range, we check if it's closer or the first enemy
-                if difference_distance < ranged.range {
-                    if let Some((_, distance)) = enemy {
-                        if difference_distance < distance {
-                            enemy = Some((difference, difference_distance));
-                        }
-                    } else {
-                        enemy = Some((difference, difference_distance));
-                    }
+        // Forget about the focus target once it's dead, and walk towards it while it's out of range
+        if let Some(focus) = ranged.focus_target {
+            match candidates
+                .iter()
+                .find(|candidate| candidate.entity == focus)
+            {
+                Some(candidate) if candidate.distance(translation) >= range => {
+                    target.update_to_vec(&candidate.position);
+                }
+                Some(_) => {
+                    target.pos = None;
+                }
+                None => {
+                    ranged.focus_target = None;
                 }
             }
+        }
+
+        let now = time.seconds_since_startup;
+        for index in 0..ranged.weapons.len() {
+            let weapon = ranged.weapons[index].modified(effects);
+            if !weapon.can_shoot(now) {
+                continue;
+            }
+
+            let indirect = weapon.kind.is_indirect();
+            let is_visible = |candidate: &TargetCandidate| {
+                indirect
+                    || has_line_of_sight(
+                        translation,
+                        candidate.position,
+                        &[entity, candidate.entity],
+                        faction.faction,
+                        &obstructions,
+                        &factions,
+                        &line_of_sight_settings,
+                    )
+            };
 
-            // If there is a closest enemy, we shoot
-            if let Some((vector, _)) = enemy {
-                Bullet::spawn(
-                    commands,
-                    &bullet_resource,
-                    time.seconds_since_startup,
+            let enemy = ranged
+                .choose_target(
+                    &weapon,
                     translation,
-                    -vector.normalize(),
                     faction.faction,
-                );
+                    &factions,
+                    &candidates,
+                    &is_visible,
+                )
+                .map(|candidate| (candidate.entity, candidate.position, candidate.velocity));
 
-                ranged.last_attack = time.seconds_since_startup;
+            // If there is a target, we shoot
+            if let Some((enemy_entity, enemy_position, enemy_velocity)) = enemy {
+                match &weapon.kind {
+                    RangedWeapon::Projectile(projectile) => {
+                        // Aim where the target will be once the bullet gets there
+                        let aim = intercept_point(
+                            translation,
+                            enemy_position,
+                            enemy_velocity,
+                            BULLET_SPEED,
+                        )
+                        .unwrap_or(enemy_position);
+
+                        bullet_pool.fire(Shot {
+                            source: entity,
+                            origin: translation,
+                            target: scatter(translation, aim, weapon.spread, &mut rng),
+                            projectile: *projectile,
+                            faction: faction.faction,
+                        })
+                    }
+                    RangedWeapon::Beam(beam) => beam.fire(
+                        &mut beam_visuals,
+                        &mut damage_requests,
+                        entity,
+                        translation,
+                        translation
+                            + (scatter(translation, enemy_position, weapon.spread, &mut rng)
+                                - translation)
+                                .normalize()
+                                * weapon.range,
+                        candidates
+                            .iter()
+                            .filter(|candidate| {
+                                candidate.entity != entity
+                                    && factions.can_damage(faction.faction, candidate.faction)
+                            })
+                            .map(|candidate| {
+                                (candidate.entity, candidate.position, candidate.size)
+                            }),
+                    ),
+                }
+
+                ranged.current_target = Some(enemy_entity);
+                ranged.weapons[index].last_attack = now;
             }
         }
     }
 }
 
+pub struct Melee {
+    pub range: f32,    // Distance between the edges of both units needed to strike
+    pub cooldown: f32, // Seconds between strikes
+    pub damage: Damage,
+    pub cleave_arc: f32, // Angle in radians around the target where other enemies also get hit
+    pub aggro_range: f32, // Distance at which the unit starts chasing enemies
+    pub on_hit: Option<StatusEffect>,
+    pub last_attack: f64,
+}
+impl Default for Melee {
+    fn default() -> Self {
+        Self {
+            range: 0.5,
+            cooldown: 0.8,
+            damage: Damage::default(),
+            cleave_arc: 0.,
+            aggro_range: 25.,
+            on_hit: None,
+            last_attack: 0.,
+        }
+    }
+}
+
+impl Melee {
+    fn can_strike(&self, current_time: f64, attack_rate: f32) -> bool {
+        self.last_attack + ((self.cooldown / attack_rate) as f64) < current_time
+    }
+
+    /// Damage per second dealt by this unit
+    fn threat(&self) -> f32 {
+        self.damage.amount as f32 / self.cooldown
+    }
+}
+
+/// Distance between the edges of two units
+fn contact_distance(a: Vec3, a_size: f32, b: Vec3, b_size: f32) -> f32 {
+    (a - b).length() - (a_size + b_size) * 0.5
+}
+
+fn melee_against_enemies(
+    time: Res<ControlledTime>,
+    factions: Res<Factions>,
+    mut damage_requests: ResMut<Events<DamageRequest>>,
+    mut melee_query: Query<
+        (
+            &mut Melee,
+            &mut TargetPosition,
+            &Transform,
+            &Faction,
+            &UnitSize,
+            Option<&StatusEffects>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    // Flying drones are out of reach of melee units
+    others_query: Query<
+        (&Unit, &Transform, &Faction, &UnitSize, Entity),
+        (Without<Dying>, Without<Drone>),
+    >,
+) {
+    let mut unit_positions = Vec::new();
+    for (_, transform, faction, size, entity) in others_query.iter() {
+        unit_positions.push((entity, transform.translation, faction.faction, size.0));
+    }
+
+    for (mut melee, mut target, transform, faction, size, effects, entity) in melee_query.iter_mut()
+    {
+        if effects.map_or(false, |effects| effects.is_stunned()) {
+            continue;
+        }
+
+        let translation = transform.translation;
+
+        // Get the closest enemy in aggro range
+        let mut enemy: Option<(Entity, Vec3, f32)> = None; // Option with (entity, position, contact_distance)
+        for (enemy_entity, enemy_translation, enemy_faction, enemy_size) in &unit_positions {
+            // Only chase enemies
+            if !factions.is_hostile(faction.faction, *enemy_faction) || *enemy_entity == entity {
+                continue;
+            }
+
+            let distance = contact_distance(translation, size.0, *enemy_translation, *enemy_size);
+            if distance < melee.aggro_range
+                && enemy.map_or(true, |(_, _, closest)| distance < closest)
+            {
+                enemy = Some((*enemy_entity, *enemy_translation, distance));
+            }
+        }
+
+        let (enemy_entity, enemy_translation, distance) = match enemy {
+            Some(enemy) => enemy,
+            None => continue,
+        };
+
+        // Close in until we're touching the enemy
+        if distance > melee.range {
+            target.update_to_vec(&enemy_translation);
+            continue;
+        }
+        target.pos = None;
+
+        let attack_rate = effects.map_or(1., |effects| effects.stat_multiplier(Stat::AttackRate));
+        if !melee.can_strike(time.seconds_since_startup, attack_rate) {
+            continue;
+        }
+
+        damage_requests.send(DamageRequest {
+            source: Some(entity),
+            target: enemy_entity,
+            damage: melee.damage,
+            cause: Cause::Melee,
+            on_hit: melee.on_hit,
+        });
+
+        // Hit every other enemy in range that's inside the cleave arc
+        if melee.cleave_arc > 0. {
+            let mut strike_direction = enemy_translation - translation;
+            strike_direction.y = 0.;
+
+            for (other_entity, other_translation, other_faction, other_size) in &unit_positions {
+                if !factions.can_damage(faction.faction, *other_faction)
+                    || *other_entity == enemy_entity
+                    || *other_entity == entity
+                {
+                    continue;
+                }
+
+                let mut direction = *other_translation - translation;
+                direction.y = 0.;
+                if contact_distance(translation, size.0, *other_translation, *other_size)
+                    <= melee.range
+                    && direction.angle_between(strike_direction) <= melee.cleave_arc * 0.5
+                {
+                    damage_requests.send(DamageRequest {
+                        source: Some(entity),
+                        target: *other_entity,
+                        damage: melee.damage,
+                        cause: Cause::Melee,
+                        on_hit: melee.on_hit,
+                    });
+                }
+            }
+        }
+
+        melee.last_attack = time.seconds_since_startup;
+    }
+}
+
 pub struct AttackPlugin;
 impl Plugin for AttackPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(shoot_against_enemies);
+        app.init_resource::<LineOfSightSettings>()
+            .add_system(shoot_against_enemies)
+            .add_system(melee_against_enemies);
     }
 }
@@ -0,0 +1,76 @@
+use crate::systems::{
+    ability::Affects,
+    death::Dying,
+    faction::{Faction, Factions},
+    status_effect::*,
+    time::*,
+};
+use bevy::prelude::*;
+
+// Seconds between two applications of the auras
+const AURA_PULSE: f64 = 0.5;
+
+/// Passive effect continuously applied to the units around its owner. The effect should last
+/// longer than a pulse and refresh, so that it only wears off once a unit leaves the aura
+#[derive(Clone, Copy, Debug)]
+pub struct Aura {
+    pub radius: f32,
+    pub affects: Affects,
+    pub effect: StatusEffect,
+}
+
+#[derive(Default)]
+pub struct Auras {
+    pub auras: Vec<Aura>,
+    next_pulse: f64,
+}
+
+impl Auras {
+    pub fn new(auras: Vec<Aura>) -> Self {
+        Self {
+            auras,
+            next_pulse: 0.,
+        }
+    }
+}
+
+fn pulse_auras(
+    time: Res<ControlledTime>,
+    factions: Res<Factions>,
+    mut status_effect_requests: ResMut<Events<StatusEffectRequest>>,
+    mut aura_query: Query<(&mut Auras, &Transform, &Faction, Entity), Without<Dying>>,
+    units_query: Query<(&Transform, &Faction, Entity), Without<Dying>>,
+) {
+    for (mut auras, transform, faction, entity) in aura_query.iter_mut() {
+        if time.seconds_since_startup < auras.next_pulse {
+            continue;
+        }
+        auras.next_pulse = time.seconds_since_startup + AURA_PULSE;
+
+        for aura in &auras.auras {
+            for (unit_transform, unit_faction, unit) in units_query.iter() {
+                let mut difference = unit_transform.translation - transform.translation;
+                difference.y = 0.;
+
+                if difference.length() <= aura.radius
+                    && aura
+                        .affects
+                        .includes(&factions, faction.faction, unit_faction.faction)
+                {
+                    status_effect_requests.send(StatusEffectRequest {
+                        source: Some(entity),
+                        target: unit,
+                        effect: aura.effect,
+                    });
+                }
+            }
+        }
+    }
+}
+
+pub struct AuraPlugin;
+impl Plugin for AuraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(pulse_auras);
+    }
+}
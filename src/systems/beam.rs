@@ -0,0 +1,97 @@
+use crate::helpers::collision::*;
+use crate::systems::{
+    bullet::UNIT_HIT_RADIUS, health::*, status_effect::StatusEffect, timed_visual::*,
+};
+use bevy::{math::Vec3, prelude::*};
+
+// Seconds the beam stays visible after firing
+const BEAM_LIFETIME: f64 = 0.1;
+const BEAM_WIDTH: f32 = 0.15;
+// Beam visuals created up front
+const BEAM_VISUAL_POOL_SIZE: usize = 64;
+
+/// Weapon that resolves instantly along a ray instead of firing a `Bullet`
+#[derive(Clone, Copy, Default)]
+pub struct Beam {
+    pub damage: Damage,
+    /// If true, the beam goes through every unit in its way instead of stopping at the first one
+    pub piercing: bool,
+    pub on_hit: Option<StatusEffect>,
+}
+
+impl Beam {
+    /// Fires from `origin` towards `end`, damaging the units in `targets` that are in the way.
+    /// `targets` should only contain units that can be hurt by the shooter
+    pub fn fire(
+        &self,
+        visuals: &mut TimedVisualPool<BeamVisual>,
+        damage_requests: &mut Events<DamageRequest>,
+        source: Entity,
+        origin: Vec3,
+        end: Vec3,
+        targets: impl Iterator<Item = (Entity, Vec3, f32)>, // (entity, position, size)
+    ) {
+        let mut hits = targets
+            .filter_map(|(entity, position, size)| {
+                segment_sphere_intersection(origin, end, position, size * UNIT_HIT_RADIUS)
+                    .map(|t| (entity, t))
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+        if !self.piercing {
+            hits.truncate(1);
+        }
+
+        for (entity, _) in &hits {
+            damage_requests.send(DamageRequest {
+                source: Some(source),
+                target: *entity,
+                damage: self.damage,
+                cause: Cause::Beam,
+                on_hit: self.on_hit,
+            });
+        }
+
+        // The beam stops at the first unit unless it goes through them
+        let visual_end = match hits.first() {
+            Some((_, t)) if !self.piercing => origin + (end - origin) * *t,
+            _ => end,
+        };
+
+        visuals.show_beam(origin, visual_end);
+    }
+}
+
+/// Ray briefly shown along a fired beam
+pub struct BeamVisual;
+
+impl TimedVisualPool<BeamVisual> {
+    /// Draws a beam between two points for a short moment
+    pub fn show_beam(&mut self, origin: Vec3, end: Vec3) {
+        let mut transform = Transform::from_translation((origin + end) * 0.5);
+        transform.look_at(end, Vec3::unit_y());
+        transform.scale = Vec3::new(BEAM_WIDTH, BEAM_WIDTH, (end - origin).length());
+        self.show(transform);
+    }
+}
+
+impl FromResources for TimedVisualPool<BeamVisual> {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut meshes = resources.get_mut::<Assets<Mesh>>().unwrap();
+        let mut materials = resources.get_mut::<Assets<StandardMaterial>>().unwrap();
+        TimedVisualPool::new(
+            // Unit cube, stretched along the length of each beam
+            meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            materials.add(Color::rgba(0.3, 0.8, 1.0, 0.8).into()),
+            BEAM_LIFETIME,
+            BEAM_VISUAL_POOL_SIZE,
+        )
+    }
+}
+
+pub struct BeamPlugin;
+impl Plugin for BeamPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(TimedVisualPlugin::<BeamVisual>::default());
+    }
+}
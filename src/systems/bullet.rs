@@ -1,80 +1,417 @@
-use crate::systems::{faction::*, health::Health, time::*, unit::*};
+use crate::helpers::{ballistics::*, collision::*};
+use crate::systems::{
+    death::Dying, faction::*, health::*, status_effect::StatusEffect, time::*, timed_visual::*,
+    unit::*,
+};
 use bevy::{math::Vec3, prelude::*};
 use bevy_contrib_colors::Tailwind;
 
-const BULLET_SPEED: f32 = 30.;
-// Seconds before the bullet is despawned
+pub const BULLET_SPEED: f32 = 30.;
+const BULLET_RADIUS: f32 = 0.3;
+// Bullets created up front, so that firing doesn't need to spawn new entities
+const BULLET_POOL_SIZE: usize = 256;
+// Seconds before the bullet is returned to the pool
 const BULLET_LIFETIME: f64 = 10.;
+// Seconds the explosion sphere stays visible
+const EXPLOSION_EFFECT_LIFETIME: f64 = 0.15;
+// Explosion spheres created up front
+const EXPLOSION_EFFECT_POOL_SIZE: usize = 32;
+// Multiplied by the UnitSize to get the radius bullets can hit a unit from
+pub const UNIT_HIT_RADIUS: f32 = 0.7;
+
+/// How a projectile travels after being fired
+#[derive(Clone, Copy)]
+pub enum Trajectory {
+    /// Flies in a straight line at `BULLET_SPEED`
+    Straight,
+    /// Arcs towards the target while being pulled down by `gravity`
+    Ballistic { gravity: f32 },
+}
+impl Default for Trajectory {
+    fn default() -> Self {
+        Trajectory::Straight
+    }
+}
+
+impl Trajectory {
+    pub fn gravity(&self) -> f32 {
+        match self {
+            Trajectory::Straight => 0.,
+            Trajectory::Ballistic { gravity } => *gravity,
+        }
+    }
+
+    /// Velocity a projectile fired from `origin` needs to reach `target`
+    pub fn launch_velocity(&self, origin: Vec3, target: Vec3, speed: f32) -> Vec3 {
+        match self {
+            Trajectory::Straight => (target - origin).normalize() * speed,
+            Trajectory::Ballistic { gravity } => {
+                ballistic_launch_velocity(origin, target, speed, *gravity)
+                    .unwrap_or_else(|| max_range_launch_velocity(origin, target, speed))
+            }
+        }
+    }
+}
+
+/// Splash damage dealt around the point where a projectile goes off
+#[derive(Clone, Copy)]
+pub struct Explosion {
+    pub radius: f32,
+    /// If true, units allied with the shooter also get hurt
+    pub friendly_fire: bool,
+    /// If true, the projectile goes off when it reaches the point it was aimed at,
+    /// even if it didn't hit anything
+    pub detonate_at_target: bool,
+}
+impl Default for Explosion {
+    fn default() -> Self {
+        Self {
+            radius: 3.,
+            friendly_fire: false,
+            detonate_at_target: false,
+        }
+    }
+}
+
+impl Explosion {
+    /// Damage taken by something `distance` away from the center, falling off linearly
+    /// to the edge of the explosion
+    pub fn damage_at(&self, damage: Damage, distance: f32) -> Option<Damage> {
+        if distance > self.radius {
+            return None;
+        }
+
+        let falloff = 1. - distance / self.radius;
+        Some(Damage {
+            amount: (damage.amount as f32 * falloff).ceil() as i16,
+            ..damage
+        })
+    }
+}
+
+/// Describes what gets fired by a weapon
+#[derive(Clone, Copy, Default)]
+pub struct Projectile {
+    pub trajectory: Trajectory,
+    pub damage: Damage,
+    pub explosion: Option<Explosion>,
+    pub on_hit: Option<StatusEffect>,
+}
 
 pub struct Bullet {
-    pub direction: Vec3,
+    // Inactive bullets are hidden in the pool, waiting to be fired again
+    pub active: bool,
+    pub source: Option<Entity>,
+    pub velocity: Vec3,
+    pub damage: Damage,
+    pub explosion: Option<Explosion>,
+    pub on_hit: Option<StatusEffect>,
+    pub detonate_at: Option<Vec3>,
+    pub gravity: f32,
+    // Position on the previous frame, used to check collisions along the travelled segment
+    pub previous_position: Vec3,
     pub should_despawn_at: f64,
 }
 impl Bullet {
-    pub fn spawn(
-        commands: &mut Commands,
-        resource: &BulletMeshResource,
-        seconds_since_startup: f64,
-        origin: Vec3,
-        direction: Vec3,
-        faction: Factions,
-    ) {
-        commands
+    fn inactive() -> Self {
+        Self {
+            active: false,
+            source: None,
+            velocity: Vec3::zero(),
+            damage: Damage::default(),
+            explosion: None,
+            on_hit: None,
+            detonate_at: None,
+            gravity: 0.,
+            previous_position: Vec3::zero(),
+            should_despawn_at: 0.,
+        }
+    }
+
+    fn from_shot(shot: &Shot, seconds_since_startup: f64) -> Self {
+        let projectile = &shot.projectile;
+        Self {
+            active: true,
+            source: Some(shot.source),
+            velocity: projectile
+                .trajectory
+                .launch_velocity(shot.origin, shot.target, BULLET_SPEED),
+            damage: projectile.damage,
+            explosion: projectile.explosion,
+            on_hit: projectile.on_hit,
+            detonate_at: projectile
+                .explosion
+                .filter(|explosion| explosion.detonate_at_target)
+                .map(|_| shot.target),
+            gravity: projectile.trajectory.gravity(),
+            previous_position: shot.origin,
+            should_despawn_at: seconds_since_startup + BULLET_LIFETIME,
+        }
+    }
+}
+
+/// A bullet waiting to be launched
+#[derive(Clone, Copy)]
+pub struct Shot {
+    pub source: Entity,
+    pub origin: Vec3,
+    pub target: Vec3,
+    pub projectile: Projectile,
+    pub faction: FactionId,
+}
+
+/// Keeps bullet entities around after they hit something or expire, so they can be fired again
+/// instead of spawning and despawning an entity for every shot
+pub struct BulletPool {
+    free: Vec<Entity>,
+    queued_shots: Vec<Shot>,
+}
+impl Default for BulletPool {
+    fn default() -> Self {
+        Self {
+            free: Vec::with_capacity(BULLET_POOL_SIZE),
+            queued_shots: Vec::with_capacity(BULLET_POOL_SIZE),
+        }
+    }
+}
+
+impl BulletPool {
+    /// Queues a bullet to be launched this frame
+    pub fn fire(&mut self, shot: Shot) {
+        self.queued_shots.push(shot);
+    }
+
+    fn release(&mut self, entity: Entity, bullet: &mut Bullet, draw: &mut Draw) {
+        bullet.active = false;
+        draw.is_visible = false;
+        self.free.push(entity);
+    }
+}
+
+fn fill_bullet_pool(
+    commands: &mut Commands,
+    resource: Res<BulletMeshResource>,
+    mut pool: ResMut<BulletPool>,
+) {
+    for _ in 0..BULLET_POOL_SIZE {
+        let entity = commands
             .spawn(PbrBundle {
                 mesh: resource.mesh.clone(),
                 material: resource.material.clone(),
-                transform: Transform::from_translation(origin),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
                 ..Default::default()
             })
-            .with(Bullet {
-                direction,
-                should_despawn_at: seconds_since_startup + BULLET_LIFETIME,
+            .with(Bullet::inactive())
+            .with(Faction::default())
+            .current_entity()
+            .unwrap();
+        pool.free.push(entity);
+    }
+}
+
+fn launch_bullets(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
+    resource: Res<BulletMeshResource>,
+    mut pool: ResMut<BulletPool>,
+    mut query: Query<(&mut Bullet, &mut Transform, &mut Faction, &mut Draw)>,
+) {
+    let BulletPool { free, queued_shots } = &mut *pool;
+
+    for shot in queued_shots.drain(..) {
+        let bullet = Bullet::from_shot(&shot, time.seconds_since_startup);
+
+        if let Some(entity) = free.pop() {
+            if let Ok((mut pooled, mut transform, mut faction, mut draw)) = query.get_mut(entity) {
+                *pooled = bullet;
+                transform.translation = shot.origin;
+                faction.faction = shot.faction;
+                draw.is_visible = true;
+                continue;
+            }
+        }
+
+        // The pool ran dry, so it grows with a new bullet that gets released into it later
+        commands
+            .spawn(PbrBundle {
+                mesh: resource.mesh.clone(),
+                material: resource.material.clone(),
+                transform: Transform::from_translation(shot.origin),
+                ..Default::default()
             })
-            .with(Faction::new(faction));
+            .with(bullet)
+            .with(Faction::new(shot.faction));
     }
 }
 
-fn move_bullet(time: Res<ControlledTime>, mut query: Query<(&Bullet, &mut Transform)>) {
-    for (bullet, mut transform) in query.iter_mut() {
-        transform.translation += BULLET_SPEED * bullet.direction * time.delta_seconds;
+fn move_bullet(time: Res<ControlledTime>, mut query: Query<(&mut Bullet, &mut Transform)>) {
+    for (mut bullet, mut transform) in query.iter_mut() {
+        if !bullet.active {
+            continue;
+        }
+
+        bullet.previous_position = transform.translation;
+
+        let gravity = bullet.gravity;
+        bullet.velocity.y -= gravity * time.delta_seconds;
+        transform.translation += bullet.velocity * time.delta_seconds;
     }
 }
 
 fn kill_after_lifetime_over(
-    commands: &mut Commands,
     time: Res<ControlledTime>,
-    query: Query<(&Bullet, Entity)>,
+    mut pool: ResMut<BulletPool>,
+    mut query: Query<(&mut Bullet, &mut Draw, Entity)>,
 ) {
-    for (bullet, entity) in query.iter() {
-        if time.seconds_since_startup >= bullet.should_despawn_at {
-            commands.despawn(entity);
+    for (mut bullet, mut draw, entity) in query.iter_mut() {
+        if bullet.active && time.seconds_since_startup >= bullet.should_despawn_at {
+            pool.release(entity, &mut bullet, &mut draw);
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn bullet_collision(
-    commands: &mut Commands,
-    bullet_query: Query<(&Bullet, &Transform, &Faction, Entity)>,
-    mut unit_query: Query<(&Unit, &Transform, &mut Health, &Faction)>,
+    factions: Res<Factions>,
+    mut pool: ResMut<BulletPool>,
+    mut explosion_visuals: ResMut<TimedVisualPool<ExplosionVisual>>,
+    mut damage_requests: ResMut<Events<DamageRequest>>,
+    mut bullet_query: Query<(&mut Bullet, &Transform, &mut Draw, &Faction, Entity)>,
+    unit_query: Query<(&Unit, &Transform, &Faction, &UnitSize, Entity), Without<Dying>>,
 ) {
-    for (_, bullet_transform, faction, bullet_entity) in bullet_query.iter() {
-        let bullet_translation = bullet_transform.translation;
+    for (mut bullet, bullet_transform, mut draw, faction, bullet_entity) in bullet_query.iter_mut()
+    {
+        // Bullets go back to the pool on their first hit, so they can never hit twice
+        if !bullet.active {
+            continue;
+        }
 
-        for (_, enemy_transform, mut health, enemy_faction) in unit_query.iter_mut() {
-            // Skip units in same faction
-            if enemy_faction.faction == faction.faction {
+        let start = bullet.previous_position;
+        let end = bullet_transform.translation;
+
+        // Find the first unit along the segment travelled this frame
+        let mut hit: Option<(Entity, f32)> = None;
+        for (_, enemy_transform, enemy_faction, size, enemy_entity) in unit_query.iter() {
+            // Skip units that can't be hurt by the shooter. Bullets start inside the shooter, so
+            // it would be hit first with friendly fire on
+            if Some(enemy_entity) == bullet.source
+                || !factions.can_damage(faction.faction, enemy_faction.faction)
+            {
                 continue;
             }
 
-            let enemy_translation = enemy_transform.translation;
-            let distance = (bullet_translation - enemy_translation).length();
-
-            if distance < 1.0 {
-                health.damage(1);
+            let radius = size.0 * UNIT_HIT_RADIUS + BULLET_RADIUS;
+            if let Some(t) =
+                segment_sphere_intersection(start, end, enemy_transform.translation, radius)
+            {
+                if hit.map_or(true, |(_, closest)| t < closest) {
+                    hit = Some((enemy_entity, t));
+                }
+            }
+        }
 
-                commands.despawn(bullet_entity);
+        let impact = if let Some((enemy_entity, t)) = hit {
+            Some((start + (end - start) * t, Some(enemy_entity)))
+        } else if let Some(detonate_at) = bullet.detonate_at {
+            // Went off once it passed the point it was aimed at
+            if (detonate_at - end).dot(end - start) <= 0. {
+                Some((detonate_at, None))
+            } else {
+                None
             }
+        } else if end.y < 0. {
+            // Arcing bullets that missed end up going through the floor
+            Some((Vec3::new(end.x, 0., end.z), None))
+        } else {
+            None
+        };
+
+        let (impact_position, enemy_entity) = match impact {
+            Some(impact) => impact,
+            None => continue,
+        };
+
+        if let Some(explosion) = bullet.explosion {
+            explode(
+                &mut explosion_visuals,
+                &factions,
+                &mut damage_requests,
+                &unit_query,
+                impact_position,
+                &explosion,
+                &bullet,
+                faction.faction,
+            );
+        } else if let Some(enemy_entity) = enemy_entity {
+            damage_requests.send(DamageRequest {
+                source: bullet.source,
+                target: enemy_entity,
+                damage: bullet.damage,
+                cause: Cause::Projectile,
+                on_hit: bullet.on_hit,
+            });
+        }
+
+        pool.release(bullet_entity, &mut bullet, &mut draw);
+    }
+}
+
+/// Damages every unit around `position`
+#[allow(clippy::too_many_arguments)]
+fn explode(
+    explosion_visuals: &mut TimedVisualPool<ExplosionVisual>,
+    factions: &Factions,
+    damage_requests: &mut Events<DamageRequest>,
+    unit_query: &Query<(&Unit, &Transform, &Faction, &UnitSize, Entity), Without<Dying>>,
+    position: Vec3,
+    explosion: &Explosion,
+    bullet: &Bullet,
+    faction: FactionId,
+) {
+    for (_, transform, unit_faction, size, entity) in unit_query.iter() {
+        if !factions.can_damage(faction, unit_faction.faction) && !explosion.friendly_fire {
+            continue;
         }
+
+        let distance = ((transform.translation - position).length() - size.0 * 0.5).max(0.);
+        if let Some(damage) = explosion.damage_at(bullet.damage, distance) {
+            damage_requests.send(DamageRequest {
+                source: bullet.source,
+                target: entity,
+                damage,
+                cause: Cause::Explosion,
+                on_hit: bullet.on_hit,
+            });
+        }
+    }
+
+    explosion_visuals.show(Transform {
+        translation: position,
+        scale: Vec3::splat(explosion.radius),
+        ..Default::default()
+    });
+}
+
+/// Sphere briefly shown where a projectile explodes
+pub struct ExplosionVisual;
+
+impl FromResources for TimedVisualPool<ExplosionVisual> {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut meshes = resources.get_mut::<Assets<Mesh>>().unwrap();
+        let mut materials = resources.get_mut::<Assets<StandardMaterial>>().unwrap();
+        TimedVisualPool::new(
+            // Unit sphere, scaled to the radius of each explosion
+            meshes.add(Mesh::from(shape::Icosphere {
+                subdivisions: 2,
+                radius: 1.0,
+            })),
+            materials.add(Color::rgba(1.0, 0.5, 0.0, 0.4).into()),
+            EXPLOSION_EFFECT_LIFETIME,
+            EXPLOSION_EFFECT_POOL_SIZE,
+        )
     }
 }
 
@@ -90,7 +427,7 @@ impl FromResources for BulletMeshResource {
         BulletMeshResource {
             mesh: meshes.add(Mesh::from(shape::Icosphere {
                 subdivisions: 4,
-                radius: 0.3,
+                radius: BULLET_RADIUS,
             })),
             material: materials.add(Tailwind::BLACK.into()),
         }
@@ -101,8 +438,12 @@ pub struct BulletPlugin;
 impl Plugin for BulletPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<BulletMeshResource>()
+            .init_resource::<BulletPool>()
+            .add_startup_system(fill_bullet_pool)
+            .add_system(launch_bullets)
             .add_system(move_bullet)
             .add_system(kill_after_lifetime_over)
-            .add_system(bullet_collision);
+            .add_system(bullet_collision)
+            .add_plugin(TimedVisualPlugin::<ExplosionVisual>::default());
     }
 }
@@ -1,5 +1,6 @@
 use crate::systems::{
     ability::*,
+    death::Dying,
     selection::Selectable,
     unit::{TargetPosition, Unit},
 };
@@ -67,7 +68,7 @@ fn switch_camera_to_entity(
     pick_state: Res<PickState>,
     mouse_button_inputs: Res<Input<MouseButton>>,
     mut camera_query: Query<&mut CameraFollow>,
-    query: Query<(&CanHaveCamera, &Unit)>,
+    query: Query<(&CanHaveCamera, &Unit), Without<Dying>>,
 ) {
     if ability.ability != Ability::SwitchCamera {
         return;
@@ -96,7 +97,7 @@ fn switch_camera_to_entity(
 fn switch_camera_back(
     mut ability: ResMut<CurrentAbility>,
     mut camera_query: Query<&mut CameraFollow>,
-    can_have_camera_query: Query<(&CanHaveCamera, &Unit, Entity)>,
+    can_have_camera_query: Query<(&CanHaveCamera, &Unit, Entity), Without<Dying>>,
 ) {
     if ability.ability != Ability::SwitchBack {
         return;
@@ -118,17 +119,20 @@ fn switch_camera_back(
     ability.ability = Ability::Select;
 }
 
-/// Switches the camera to the previous or a random entity if the current one dies
+/// Switches the camera to the previous or a random entity once the current one is removed.
+/// The camera stays on dying units until their death sequence is over
 fn switch_after_current_unit_dies(
     mut camera_query: Query<&mut CameraFollow>,
-    can_have_camera_query: Query<(&CanHaveCamera, &Unit, Entity)>,
+    can_have_camera_query: Query<(&CanHaveCamera, &Unit, Entity), Without<Dying>>,
+    dying_query: Query<&Dying>,
 ) {
     for mut camera_follow in camera_query.iter_mut() {
         if let Some(following) = camera_follow.entity {
-            // If the unit is not in the query, it has died, so we need to change it
+            // If the unit is not in the query and not dying, it has been removed, so we need to change it
             if can_have_camera_query
                 .get_component::<CanHaveCamera>(following)
                 .is_err()
+                && dying_query.get(following).is_err()
             {
                 // Check if prev_entity is valid, and go to that one if it is
                 if let Some(prev) = camera_follow.previous_entity {
@@ -0,0 +1,334 @@
+use crate::helpers::billboard::BillboardCamera;
+use crate::systems::{
+    ability::*,
+    beam::BeamVisual,
+    camera::CameraFollow,
+    death::Dying,
+    health::DamageEvent,
+    status_effect::StatusEffects,
+    time::*,
+    timed_visual::TimedVisualPool,
+    unit::{TargetPosition, UnitSize},
+};
+use bevy::prelude::*;
+
+// How far a caster can be pushed around before its cast breaks
+const INTERRUPT_DISTANCE: f32 = 0.5;
+const CAST_BAR_WIDTH: f32 = 2.;
+const CAST_BAR_HEIGHT: f32 = 0.2;
+
+/// Ability that a unit is winding up or channelling. Moving, getting stunned or taking damage
+/// interrupts it
+pub struct Casting {
+    pub ability: AbilityId,
+    pub target: CastTarget,
+    started_at: f64,
+    cast_time: f32,
+    channel: Option<Channel>,
+    next_tick: f64,
+    position: Vec3, // Where the caster stood when it started casting
+    has_bar: bool,
+}
+
+impl Casting {
+    fn channel_start(&self) -> f64 {
+        self.started_at + self.cast_time as f64
+    }
+
+    /// How full the cast bar is: it fills up during the cast time, then empties while
+    /// channelling
+    fn bar_fill(&self, current_time: f64) -> f32 {
+        let channel_start = self.channel_start();
+        let fill = if current_time < channel_start {
+            (current_time - self.started_at) / self.cast_time as f64
+        } else {
+            match self.channel {
+                Some(channel) => 1. - (current_time - channel_start) / channel.duration as f64,
+                None => 1.,
+            }
+        };
+        fill.max(0.).min(1.) as f32
+    }
+}
+
+#[derive(Default)]
+struct State {
+    ability_cast_event_reader: EventReader<AbilityCastEvent>,
+    damage_event_reader: EventReader<DamageEvent>,
+}
+
+/// Pays for the abilities that got cast. Instant ones resolve right away, the others start
+/// winding up
+#[allow(clippy::too_many_arguments)]
+fn start_casts(
+    commands: &mut Commands,
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    registry: Res<AbilityRegistry>,
+    cast_events: Res<Events<AbilityCastEvent>>,
+    mut resolve_events: ResMut<Events<AbilityResolveEvent>>,
+    mut query: Query<
+        (
+            &mut UnitAbilities,
+            Option<&mut Energy>,
+            &Transform,
+            &mut TargetPosition,
+        ),
+        Without<Dying>,
+    >,
+) {
+    for event in state.ability_cast_event_reader.iter(&cast_events) {
+        let definition = match registry.get(event.ability) {
+            Some(definition) => definition,
+            None => continue,
+        };
+
+        // Pay for the ability, and skip it if it can't be afforded anymore
+        let (mut abilities, mut energy, transform, mut target_position) =
+            match query.get_mut(event.caster) {
+                Ok(caster) => caster,
+                Err(_) => continue,
+            };
+        if !abilities.start_cast(
+            definition,
+            energy.as_deref_mut(),
+            time.seconds_since_startup,
+        ) {
+            continue;
+        }
+
+        if definition.cast_time <= 0. && definition.channel.is_none() {
+            resolve_events.send(AbilityResolveEvent {
+                ability: event.ability,
+                caster: event.caster,
+                target: event.target,
+            });
+            continue;
+        }
+
+        // Casters stand still while casting
+        target_position.pos = None;
+        commands.insert_one(
+            event.caster,
+            Casting {
+                ability: event.ability,
+                target: event.target,
+                started_at: time.seconds_since_startup,
+                cast_time: definition.cast_time,
+                channel: definition.channel,
+                next_tick: time.seconds_since_startup + definition.cast_time as f64,
+                position: transform.translation,
+                has_bar: false,
+            },
+        );
+    }
+}
+
+/// Resolves casts once they're wound up, ticks channels, and interrupts them when needed
+#[allow(clippy::too_many_arguments)]
+fn advance_casts(
+    commands: &mut Commands,
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    registry: Res<AbilityRegistry>,
+    mut beam_visuals: ResMut<TimedVisualPool<BeamVisual>>,
+    damage_events: Res<Events<DamageEvent>>,
+    mut resolve_events: ResMut<Events<AbilityResolveEvent>>,
+    mut query: Query<(
+        &mut Casting,
+        &Transform,
+        Option<&StatusEffects>,
+        Option<&Dying>,
+        Entity,
+    )>,
+    target_query: Query<&Transform, Without<Dying>>,
+) {
+    let damaged: Vec<Entity> = state
+        .damage_event_reader
+        .iter(&damage_events)
+        .filter(|event| event.amount > 0)
+        .map(|event| event.target)
+        .collect();
+
+    for (mut casting, transform, effects, dying, entity) in query.iter_mut() {
+        let moved = (transform.translation - casting.position).length() > INTERRUPT_DISTANCE;
+        let stunned = effects.map_or(false, |effects| effects.is_stunned());
+        if moved || stunned || dying.is_some() || damaged.contains(&entity) {
+            commands.remove_one::<Casting>(entity);
+            continue;
+        }
+
+        // Units targeted by the cast have to stay alive and in range
+        let target_position = match casting.target {
+            CastTarget::Unit(target) => match target_query.get(target) {
+                Ok(target_transform) => Some(target_transform.translation),
+                Err(_) => {
+                    commands.remove_one::<Casting>(entity);
+                    continue;
+                }
+            },
+            _ => None,
+        };
+        if let (Some(definition), Some(target_position)) =
+            (registry.get(casting.ability), target_position)
+        {
+            if !in_cast_range(transform.translation, target_position, definition) {
+                commands.remove_one::<Casting>(entity);
+                continue;
+            }
+        }
+
+        let current_time = time.seconds_since_startup;
+        if current_time < casting.channel_start() {
+            continue;
+        }
+
+        let channel = match casting.channel {
+            Some(channel) => channel,
+            None => {
+                resolve_events.send(AbilityResolveEvent {
+                    ability: casting.ability,
+                    caster: entity,
+                    target: casting.target,
+                });
+                commands.remove_one::<Casting>(entity);
+                continue;
+            }
+        };
+
+        let channel_end = casting.channel_start() + channel.duration as f64;
+        while casting.next_tick <= current_time && casting.next_tick < channel_end {
+            resolve_events.send(AbilityResolveEvent {
+                ability: casting.ability,
+                caster: entity,
+                target: casting.target,
+            });
+            casting.next_tick += channel.interval as f64;
+
+            if let Some(target_position) = target_position {
+                beam_visuals.show_beam(transform.translation, target_position);
+            }
+        }
+        if current_time >= channel_end {
+            commands.remove_one::<Casting>(entity);
+        }
+    }
+}
+
+struct CastBar {
+    owner: Entity,
+    started_at: f64, // Identifies the cast the bar belongs to
+    fill: bool,
+}
+
+fn spawn_cast_bars(
+    commands: &mut Commands,
+    materials: Res<CastBarMaterials>,
+    mut query: Query<(&mut Casting, Entity)>,
+) {
+    for (mut casting, entity) in query.iter_mut() {
+        if casting.has_bar {
+            continue;
+        }
+        casting.has_bar = true;
+
+        for fill in [false, true].iter() {
+            commands
+                .spawn(SpriteBundle {
+                    material: if *fill {
+                        materials.cast.clone()
+                    } else {
+                        materials.background.clone()
+                    },
+                    sprite: Sprite {
+                        size: Vec2::new(1.0, 1.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(CastBar {
+                    owner: entity,
+                    started_at: casting.started_at,
+                    fill: *fill,
+                });
+        }
+    }
+}
+
+/// Shows how far along each cast is, above the caster
+fn update_cast_bars(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
+    materials: Res<CastBarMaterials>,
+    mut bar_query: Query<(&CastBar, &mut Transform, &mut Handle<ColorMaterial>, Entity)>,
+    owner_query: Query<(&Casting, &Transform, &UnitSize)>,
+    camera_query: Query<(&CameraFollow, &Transform)>,
+) {
+    let camera = match BillboardCamera::find(&camera_query) {
+        Some(camera) => camera,
+        None => return,
+    };
+
+    for (bar, mut transform, mut material, entity) in bar_query.iter_mut() {
+        let (casting, owner_transform, size) = match owner_query.get(bar.owner) {
+            Ok(owner) if owner.0.started_at == bar.started_at => owner,
+            // The cast is over
+            _ => {
+                commands.despawn(entity);
+                continue;
+            }
+        };
+
+        let position = owner_transform.translation + Vec3::unit_y() * (size.0 + 1.);
+        let towards_camera = (camera.transform.translation - position).normalize();
+        camera.place(&mut transform, position);
+        transform.scale = Vec3::new(CAST_BAR_WIDTH, CAST_BAR_HEIGHT, 1.);
+
+        if bar.fill {
+            let fill = casting.bar_fill(time.seconds_since_startup);
+            // Anchored on the left, slightly in front of the background
+            transform.translation +=
+                towards_camera * 0.01 - camera.right * (1. - fill) * CAST_BAR_WIDTH * 0.5;
+            transform.scale.x = fill * CAST_BAR_WIDTH;
+
+            let channelling = time.seconds_since_startup >= casting.channel_start();
+            let wanted = if channelling {
+                &materials.channel
+            } else {
+                &materials.cast
+            };
+            if *material != *wanted {
+                *material = wanted.clone();
+            }
+        }
+    }
+}
+
+struct CastBarMaterials {
+    background: Handle<ColorMaterial>,
+    cast: Handle<ColorMaterial>,
+    channel: Handle<ColorMaterial>,
+}
+
+impl FromResources for CastBarMaterials {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut materials = resources.get_mut::<Assets<ColorMaterial>>().unwrap();
+        CastBarMaterials {
+            background: materials.add(Color::rgb(0.1, 0.1, 0.1).into()),
+            cast: materials.add(Color::rgb(1.0, 0.8, 0.2).into()),
+            channel: materials.add(Color::rgb(0.3, 0.8, 1.0).into()),
+        }
+    }
+}
+
+pub struct CastingPlugin;
+impl Plugin for CastingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<State>()
+            .init_resource::<CastBarMaterials>()
+            .add_system(start_casts)
+            .add_system(advance_casts)
+            .add_system(spawn_cast_bars)
+            .add_system(update_cast_bars);
+    }
+}
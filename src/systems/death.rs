@@ -0,0 +1,248 @@
+use crate::systems::{health::*, selection::Selectable, time::*, unit::UnitSize};
+use bevy::prelude::*;
+use bevy_contrib_colors::Tailwind;
+use rand::Rng;
+
+const DEBRIS_PIECES: usize = 8;
+const DEBRIS_SPEED: f32 = 8.;
+const DEBRIS_GRAVITY: f32 = 20.;
+
+/// How a unit looks while it's dying
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DeathStyle {
+    /// Slowly goes down into the ground
+    Sink,
+    /// Becomes transparent
+    Fade,
+    /// Breaks into pieces that fly around
+    Debris,
+}
+
+/// Configures the death sequence of a unit
+#[derive(Clone, Copy)]
+pub struct DeathAnimation {
+    pub style: DeathStyle,
+    pub duration: f32, // Seconds between dying and being removed
+}
+impl Default for DeathAnimation {
+    fn default() -> Self {
+        Self {
+            style: DeathStyle::Sink,
+            duration: 1.5,
+        }
+    }
+}
+
+/// Marks a unit that died and is playing its death sequence.
+/// Dying units can't be targeted, selected or controlled
+pub struct Dying {
+    animation: DeathAnimation,
+    started_at: f64,
+    start_translation: Vec3,
+    start_scale: Vec3,
+}
+
+impl Dying {
+    /// How far along the death sequence is, from 0 to 1
+    fn progress(&self, current_time: f64) -> f32 {
+        ((current_time - self.started_at) as f32 / self.animation.duration).min(1.)
+    }
+}
+
+struct DebrisPiece {
+    velocity: Vec3,
+    should_despawn_at: f64,
+}
+
+#[derive(Default)]
+struct State {
+    death_event_reader: EventReader<DeathEvent>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_dying(
+    commands: &mut Commands,
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    death_events: Res<Events<DeathEvent>>,
+    resource: Res<DeathResource>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<
+        (
+            &Transform,
+            &UnitSize,
+            Option<&DeathAnimation>,
+            Option<&mut Selectable>,
+            Option<&Handle<StandardMaterial>>,
+        ),
+        Without<Dying>,
+    >,
+) {
+    let mut rng = rand::thread_rng();
+    // Dying only gets inserted at the end of the frame, so a unit can't start dying twice
+    let mut started = Vec::new();
+    for event in state.death_event_reader.iter(&death_events) {
+        let entity = event.target;
+        if started.contains(&entity) {
+            continue;
+        }
+        started.push(entity);
+        let (transform, size, animation, selectable, material) = match query.get_mut(entity) {
+            Ok(unit) => unit,
+            Err(_) => continue,
+        };
+        let animation = animation.copied().unwrap_or_default();
+
+        if let Some(mut selectable) = selectable {
+            selectable.set_selected(false);
+        }
+
+        match animation.style {
+            DeathStyle::Sink => {}
+            DeathStyle::Fade => {
+                // The material is shared with other units, so the fading one gets its own copy
+                if let Some(albedo) = material
+                    .and_then(|material| materials.get(material))
+                    .map(|material| material.albedo)
+                {
+                    let faded = materials.add(albedo.into());
+                    commands.insert_one(entity, faded);
+                }
+            }
+            DeathStyle::Debris => {
+                for _ in 0..DEBRIS_PIECES {
+                    let direction = Vec3::new(
+                        rng.gen_range(-1., 1.),
+                        rng.gen_range(0.5, 1.5),
+                        rng.gen_range(-1., 1.),
+                    );
+                    commands
+                        .spawn(PbrBundle {
+                            mesh: resource.debris_mesh.clone(),
+                            material: resource.debris_material.clone(),
+                            transform: Transform {
+                                translation: transform.translation,
+                                scale: Vec3::splat(size.0),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .with(DebrisPiece {
+                            velocity: direction * DEBRIS_SPEED,
+                            should_despawn_at: time.seconds_since_startup
+                                + animation.duration as f64,
+                        });
+                }
+            }
+        }
+
+        commands.insert_one(
+            entity,
+            Dying {
+                animation,
+                started_at: time.seconds_since_startup,
+                start_translation: transform.translation,
+                start_scale: transform.scale,
+            },
+        );
+    }
+}
+
+fn animate_dying(
+    time: Res<ControlledTime>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<(
+        &Dying,
+        &UnitSize,
+        &mut Transform,
+        Option<&Handle<StandardMaterial>>,
+    )>,
+) {
+    for (dying, size, mut transform, material) in query.iter_mut() {
+        let progress = dying.progress(time.seconds_since_startup);
+        match dying.animation.style {
+            DeathStyle::Sink => {
+                transform.translation =
+                    dying.start_translation - Vec3::unit_y() * (size.0 + 1.) * progress;
+            }
+            DeathStyle::Fade => {
+                if let Some(material) = material.and_then(|material| materials.get_mut(material)) {
+                    material.albedo.set_a(1. - progress);
+                }
+            }
+            DeathStyle::Debris => {
+                // The unit falls apart right away, leaving only the pieces
+                transform.scale = dying.start_scale * (1. - progress * 5.).max(0.);
+            }
+        }
+    }
+}
+
+fn move_debris(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
+    mut query: Query<(&mut DebrisPiece, &mut Transform, Entity)>,
+) {
+    for (mut piece, mut transform, entity) in query.iter_mut() {
+        if piece.should_despawn_at <= time.seconds_since_startup {
+            commands.despawn(entity);
+            continue;
+        }
+
+        piece.velocity.y -= DEBRIS_GRAVITY * time.delta_seconds;
+        transform.translation += piece.velocity * time.delta_seconds;
+        if transform.translation.y < 0. {
+            transform.translation.y = 0.;
+            piece.velocity = Vec3::zero();
+        }
+    }
+}
+
+/// Removes units once their death sequence is over, along with their selection circle.
+/// Cameras following them switch to another unit on their own
+fn remove_dead(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
+    query: Query<(&Dying, Option<&Selectable>, Entity)>,
+) {
+    for (dying, option_selectable, entity) in query.iter() {
+        if dying.progress(time.seconds_since_startup) < 1. {
+            continue;
+        }
+
+        // If it's a selectable, despawn it's circle too
+        if let Some(selectable) = option_selectable {
+            commands.despawn(selectable.circle);
+        }
+
+        commands.despawn(entity);
+    }
+}
+
+struct DeathResource {
+    debris_mesh: Handle<Mesh>,
+    debris_material: Handle<StandardMaterial>,
+}
+
+impl FromResources for DeathResource {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut meshes = resources.get_mut::<Assets<Mesh>>().unwrap();
+        let mut materials = resources.get_mut::<Assets<StandardMaterial>>().unwrap();
+        DeathResource {
+            debris_mesh: meshes.add(Mesh::from(shape::Cube { size: 0.25 })),
+            debris_material: materials.add(Tailwind::GRAY700.into()),
+        }
+    }
+}
+
+pub struct DeathPlugin;
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<State>()
+            .init_resource::<DeathResource>()
+            .add_system(start_dying)
+            .add_system(animate_dying)
+            .add_system(move_debris)
+            .add_system(remove_dead);
+    }
+}
@@ -1,21 +1,17 @@
 use crate::helpers::shapes::*;
-use crate::systems::{ability::*, health::*, selection::*, unit::*};
+use crate::systems::{ability::*, death::Dying, selection::*};
 use bevy::prelude::*;
 use bevy_mod_picking::*;
 
 pub struct SelectionState {
     initial_position: Option<Vec3>,
     current_position: Option<Vec3>,
-
-    last_rectangle: Option<(Vec3, Vec3)>,
 }
 impl Default for SelectionState {
     fn default() -> Self {
         Self {
             initial_position: None,
             current_position: None,
-
-            last_rectangle: None,
         }
     }
 }
@@ -51,7 +47,7 @@ fn start_drag_select(
     pick_state: Res<PickState>,
     mouse_button_inputs: Res<Input<MouseButton>>,
 ) {
-    if ability.ability != Ability::Select && ability.ability != Ability::HealArea {
+    if ability.ability != Ability::Select {
         return;
     }
 
@@ -73,20 +69,12 @@ fn finish_drag_select(
     mut selection_state: ResMut<SelectionState>,
     mouse_button_inputs: Res<Input<MouseButton>>,
 ) {
-    if ability.ability != Ability::Select && ability.ability != Ability::HealArea {
+    if ability.ability != Ability::Select {
         return;
     }
 
-    // If we release the button, save the rectangle and reset the values
+    // If we release the button, reset the values
     if mouse_button_inputs.just_released(MouseButton::Left) {
-        if ability.ability == Ability::HealArea {
-            if let Some(initial_position) = selection_state.initial_position {
-                if let Some(current_position) = selection_state.current_position {
-                    selection_state.last_rectangle = Some((initial_position, current_position));
-                }
-            }
-        }
-
         selection_state.initial_position = None;
         selection_state.current_position = None;
     }
@@ -99,7 +87,7 @@ fn drag_select(
     mut meshes: ResMut<Assets<Mesh>>,
     mut drag_selection_rectangle: Query<(&Handle<Mesh>, &DragSelectionRectangle, &mut Draw)>,
 ) {
-    if ability.ability != Ability::Select && ability.ability != Ability::HealArea {
+    if ability.ability != Ability::Select {
         return;
     }
 
@@ -137,7 +125,7 @@ fn is_between_two_values(x: f32, a: f32, b: f32) -> bool {
 fn select_inside_rectangle(
     selection_state: Res<SelectionState>,
     ability: Res<CurrentAbility>,
-    mut query: Query<(&mut Selectable, &Transform)>,
+    mut query: Query<(&mut Selectable, &Transform), Without<Dying>>,
 ) {
     if ability.ability != Ability::Select {
         return;
@@ -164,30 +152,6 @@ fn select_inside_rectangle(
     }
 }
 
-fn heal_area_ability(
-    mut selection_state: ResMut<SelectionState>,
-    mut ability: ResMut<CurrentAbility>,
-    mut query: Query<(&mut Health, &Unit, &Transform)>,
-) {
-    if ability.ability != Ability::HealArea {
-        return;
-    }
-
-    if let Some((beginning, end)) = selection_state.last_rectangle {
-        for (mut health, _unit, transform) in query.iter_mut() {
-            // Heal the units inside the rectangle
-            if is_between_two_values(transform.translation.x, beginning.x, end.x)
-                && is_between_two_values(transform.translation.z, beginning.z, end.z)
-            {
-                health.heal(3);
-            }
-        }
-
-        selection_state.last_rectangle = None;
-        ability.ability = Ability::Select;
-    }
-}
-
 pub struct DragSelectionPlugin;
 impl Plugin for DragSelectionPlugin {
     fn build(&self, app: &mut AppBuilder) {
@@ -196,7 +160,6 @@ impl Plugin for DragSelectionPlugin {
             .add_system(finish_drag_select)
             .add_system(drag_select)
             .add_system(select_inside_rectangle)
-            .add_startup_system(create_drag_rectangle)
-            .add_system(heal_area_ability);
+            .add_startup_system(create_drag_rectangle);
     }
 }
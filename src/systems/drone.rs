@@ -1,5 +1,5 @@
 use crate::helpers::movement::*;
-use crate::systems::{camera::*, time::*};
+use crate::systems::{camera::*, death::Dying, time::*};
 use bevy::{input::mouse::MouseMotion, prelude::*, render::camera::Camera};
 
 // From https://github.com/mcpar-land/bevy_fly_camera/blob/master/src/lib.rs
@@ -41,7 +41,7 @@ fn drone_movement_system(
     time: Res<ControlledTime>,
     keyboard_input: Res<Input<KeyCode>>,
     camera_query: Query<(&Camera, &CameraFollow)>,
-    mut can_have_camera_query: Query<(&mut Drone, &CanHaveCamera, &mut Transform)>,
+    mut can_have_camera_query: Query<(&mut Drone, &CanHaveCamera, &mut Transform), Without<Dying>>,
 ) {
     for (_, camera_follow) in camera_query.iter() {
         if let Some(following) = camera_follow.entity {
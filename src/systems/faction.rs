@@ -1,20 +1,113 @@
-#[derive(PartialEq, Copy, Clone)]
-pub enum Factions {
-    Player,
-    Aliens,
+use bevy::prelude::*;
+
+/// Identifies one of the factions registered in `Factions`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct FactionId(usize);
+impl FactionId {
+    pub const PLAYER: FactionId = FactionId(0);
+    pub const ALIENS: FactionId = FactionId(1);
+}
+impl Default for FactionId {
+    fn default() -> Self {
+        FactionId::PLAYER
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Relation {
+    Allied,
+    Neutral,
+    Hostile,
+}
+
+/// Every faction in the game and how they feel about each other.
+/// All hostility checks should go through this
+pub struct Factions {
+    names: Vec<String>,
+    // Square matrix, indexed by both faction ids
+    relations: Vec<Vec<Relation>>,
+    /// If true, units also hurt units of factions they're allied with
+    pub friendly_fire: bool,
 }
 impl Default for Factions {
     fn default() -> Self {
-        Factions::Player
+        let mut factions = Self {
+            names: Vec::new(),
+            relations: Vec::new(),
+            friendly_fire: false,
+        };
+
+        let player = factions.add_faction("Player");
+        let aliens = factions.add_faction("Aliens");
+        factions.set_relation(player, aliens, Relation::Hostile);
+
+        factions
+    }
+}
+
+impl Factions {
+    /// Registers a new faction, which starts neutral to every other one
+    pub fn add_faction(&mut self, name: &str) -> FactionId {
+        let id = FactionId(self.names.len());
+        self.names.push(name.to_string());
+
+        for row in &mut self.relations {
+            row.push(Relation::Neutral);
+        }
+        let mut row = vec![Relation::Neutral; self.names.len()];
+        row[id.0] = Relation::Allied;
+        self.relations.push(row);
+
+        id
+    }
+
+    pub fn name(&self, id: FactionId) -> &str {
+        &self.names[id.0]
+    }
+
+    pub fn relation(&self, a: FactionId, b: FactionId) -> Relation {
+        self.relations[a.0][b.0]
+    }
+
+    /// Relations always go both ways
+    pub fn set_relation(&mut self, a: FactionId, b: FactionId, relation: Relation) {
+        // A faction is always allied with itself
+        if a == b {
+            return;
+        }
+
+        self.relations[a.0][b.0] = relation;
+        self.relations[b.0][a.0] = relation;
+    }
+
+    /// Whether units of `a` should attack units of `b`
+    pub fn is_hostile(&self, a: FactionId, b: FactionId) -> bool {
+        self.relation(a, b) == Relation::Hostile
+    }
+
+    pub fn is_allied(&self, a: FactionId, b: FactionId) -> bool {
+        self.relation(a, b) == Relation::Allied
+    }
+
+    /// Whether something fired by `attacker` hurts units of `target` when it hits them
+    pub fn can_damage(&self, attacker: FactionId, target: FactionId) -> bool {
+        self.friendly_fire || !self.is_allied(attacker, target)
     }
 }
 
 #[derive(Default)]
 pub struct Faction {
-    pub faction: Factions,
+    pub faction: FactionId,
 }
 impl Faction {
-    pub fn new(faction: Factions) -> Self {
+    pub fn new(faction: FactionId) -> Self {
         Self { faction }
     }
 }
+
+pub struct FactionPlugin;
+impl Plugin for FactionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Factions>();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::prefabs::Prefab;
+use crate::systems::{ability::*, status_effect::*, summon::Summon};
+use bevy::prelude::*;
+
+pub const TELEPORT: AbilityId = "teleport";
+pub const HEAL_UNIT: AbilityId = "heal-unit";
+pub const HEAL_AREA: AbilityId = "heal-area";
+pub const HEALING_WAVE: AbilityId = "healing-wave";
+pub const BARRIER: AbilityId = "barrier";
+pub const MENDING_BEAM: AbilityId = "mending-beam";
+pub const DEPLOY_DRONE: AbilityId = "deploy-drone";
+
+/// Registers the abilities of the teleporting healer
+pub struct HealerPlugin;
+impl Plugin for HealerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_ability(AbilityDefinition {
+            id: TELEPORT,
+            name: "Teleport".to_string(),
+            targeting: Targeting::Point,
+            affects: Affects::All,
+            range: 60.,
+            cost: 30.,
+            cooldown: 8.,
+            cast_time: 0.,
+            channel: None,
+            autocast: None,
+            effects: vec![AbilityEffect::TeleportCaster],
+        })
+        .register_ability(AbilityDefinition {
+            id: HEAL_UNIT,
+            name: "Heal".to_string(),
+            targeting: Targeting::Unit,
+            affects: Affects::Allies,
+            range: 25.,
+            cost: 20.,
+            cooldown: 3.,
+            cast_time: 0.,
+            channel: None,
+            // Keeps badly hurt allies alive without the player having to click
+            autocast: Some(AutocastRule::LowestHealthBelow(0.5)),
+            effects: vec![
+                AbilityEffect::Heal(20),
+                AbilityEffect::ApplyStatus(StatusEffect {
+                    id: "heal-shield",
+                    kind: EffectKind::Shield { amount: 5 },
+                    duration: 8.,
+                    stacking: Stacking::Refresh,
+                }),
+            ],
+        })
+        .register_ability(AbilityDefinition {
+            id: HEAL_AREA,
+            name: "Heal area".to_string(),
+            targeting: Targeting::Area { radius: 6. },
+            affects: Affects::Allies,
+            range: 30.,
+            cost: 35.,
+            cooldown: 10.,
+            cast_time: 1.,
+            channel: None,
+            autocast: None,
+            effects: vec![
+                AbilityEffect::Heal(3),
+                // Keeps healing for a while afterwards
+                AbilityEffect::ApplyStatus(StatusEffect {
+                    id: "regeneration",
+                    kind: EffectKind::HealOverTime {
+                        amount: 1,
+                        interval: 1.,
+                    },
+                    duration: 5.,
+                    stacking: Stacking::Refresh,
+                }),
+            ],
+        })
+        .register_ability(AbilityDefinition {
+            id: HEALING_WAVE,
+            name: "Healing wave".to_string(),
+            targeting: Targeting::Cone {
+                length: 15.,
+                angle: std::f32::consts::FRAC_PI_3,
+            },
+            affects: Affects::Allies,
+            range: 0.,
+            cost: 25.,
+            cooldown: 6.,
+            cast_time: 0.,
+            channel: None,
+            autocast: None,
+            effects: vec![AbilityEffect::Heal(8)],
+        })
+        .register_ability(AbilityDefinition {
+            id: BARRIER,
+            name: "Barrier".to_string(),
+            targeting: Targeting::OnSelf,
+            affects: Affects::Allies,
+            range: 0.,
+            cost: 25.,
+            cooldown: 15.,
+            cast_time: 0.,
+            channel: None,
+            autocast: None,
+            effects: vec![AbilityEffect::ApplyStatus(StatusEffect {
+                id: "barrier",
+                kind: EffectKind::Shield { amount: 10 },
+                duration: 10.,
+                stacking: Stacking::Refresh,
+            })],
+        })
+        // Heals a unit for as long as the healer keeps channelling
+        .register_ability(AbilityDefinition {
+            id: MENDING_BEAM,
+            name: "Mending beam".to_string(),
+            targeting: Targeting::Unit,
+            affects: Affects::Allies,
+            range: 20.,
+            cost: 30.,
+            cooldown: 12.,
+            cast_time: 0.,
+            channel: Some(Channel {
+                duration: 4.,
+                interval: 0.5,
+            }),
+            autocast: None,
+            effects: vec![AbilityEffect::Heal(3)],
+        })
+        .register_ability(AbilityDefinition {
+            id: DEPLOY_DRONE,
+            name: "Deploy drone".to_string(),
+            targeting: Targeting::Point,
+            affects: Affects::All,
+            range: 20.,
+            cost: 50.,
+            cooldown: 20.,
+            cast_time: 1.5,
+            channel: None,
+            autocast: None,
+            effects: vec![AbilityEffect::Summon(Summon {
+                prefab: Prefab::Drone,
+                lifetime: Some(30.),
+                cap: 2,
+            })],
+        });
+    }
+}
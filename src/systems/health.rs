@@ -1,11 +1,12 @@
-use crate::systems::{ability::*, selection::Selectable};
-use crate::ui::*;
+use crate::systems::{
+    status_effect::{StatusEffect, StatusEffects},
+    time::ControlledTime,
+};
 use bevy::prelude::*;
 
 pub struct Health {
     pub value: i16,
     max_health: i16,
-    previous_value: i16, // Used for the displayed number
 }
 
 impl Default for Health {
@@ -13,7 +14,6 @@ impl Default for Health {
         Self {
             value: 3,
             max_health: 3,
-            previous_value: 3,
         }
     }
 }
@@ -23,58 +23,246 @@ impl Health {
         Self {
             value,
             max_health: value,
-            previous_value: value,
         }
     }
 
+    pub fn max_health(&self) -> i16 {
+        self.max_health
+    }
+
     pub fn damage(&mut self, value: i16) {
-        self.previous_value = self.value;
         self.value = (self.value - value).min(self.max_health);
     }
 
-    pub fn heal(&mut self, value: i16) {
-        self.previous_value = self.value;
+    /// Returns how much health was actually restored
+    pub fn heal(&mut self, value: i16) -> i16 {
+        let previous_value = self.value;
         self.value = (self.value + value).min(self.max_health);
+        self.value - previous_value
     }
+}
+
+/// What caused a change in health
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cause {
+    Projectile,
+    Explosion,
+    Beam,
+    Melee,
+    Ability,
+    StatusEffect,
+    /// Summoned units whose time ran out
+    Expired,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DamageType {
+    Kinetic,
+    Energy,
+    Explosive,
+}
 
-    pub fn difference(&self) -> i16 {
-        self.value - self.previous_value
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Damage {
+    pub amount: i16,
+    pub damage_type: DamageType,
+}
+impl Default for Damage {
+    fn default() -> Self {
+        Self {
+            amount: 1,
+            damage_type: DamageType::Kinetic,
+        }
+    }
+}
+impl Damage {
+    pub fn new(amount: i16, damage_type: DamageType) -> Self {
+        Self {
+            amount,
+            damage_type,
+        }
     }
 }
 
-fn kill_if_health_0(commands: &mut Commands, query: Query<(&Health, Entity), Mutated<Health>>) {
-    for (health, entity) in &mut query.iter() {
-        if health.value <= 0 {
-            commands.insert_one(entity, Dead {});
+/// Flat reduction applied to every hit after resistances
+#[derive(Default)]
+pub struct Armor(pub i16);
+
+/// Multipliers applied to incoming damage of each type. `1.` takes full damage, `0.` is immune
+pub struct Resistances {
+    pub kinetic: f32,
+    pub energy: f32,
+    pub explosive: f32,
+}
+impl Default for Resistances {
+    fn default() -> Self {
+        Self {
+            kinetic: 1.,
+            energy: 1.,
+            explosive: 1.,
         }
     }
 }
+impl Resistances {
+    pub fn multiplier(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Kinetic => self.kinetic,
+            DamageType::Energy => self.energy,
+            DamageType::Explosive => self.explosive,
+        }
+    }
+}
+
+/// Final amount of damage taken after applying resistances and armor.
+/// Any hit that isn't fully resisted does at least 1 damage
+pub fn mitigate_damage(
+    damage: Damage,
+    armor: Option<&Armor>,
+    resistances: Option<&Resistances>,
+) -> i16 {
+    let multiplier =
+        resistances.map_or(1., |resistances| resistances.multiplier(damage.damage_type));
+    let resisted = (damage.amount as f32 * multiplier).round() as i16;
+    if resisted <= 0 {
+        return 0;
+    }
+
+    (resisted - armor.map_or(0, |armor| armor.0)).max(1)
+}
 
-pub struct Dead;
-fn remove_if_dead(
-    commands: &mut Commands,
-    mut buttons: ResMut<AvailableButtons>,
-    query: Query<(&Dead, Entity, Option<&Selectable>, Option<&UnitAbilities>)>,
+/// Sent by anything that wants to damage a unit. Every source of damage goes through these,
+/// so that armor and resistances are applied in a single place
+pub struct DamageRequest {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub damage: Damage,
+    pub cause: Cause,
+    // Status effect put on the target by the hit
+    pub on_hit: Option<StatusEffect>,
+}
+
+/// Sent by anything that wants to heal a unit
+pub struct HealRequest {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: i16,
+    pub cause: Cause,
+}
+
+/// Sent after a unit took damage, with the amount left after armor and resistances
+pub struct DamageEvent {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: i16,
+    pub cause: Cause,
+}
+
+/// Sent after a unit was healed, with the amount of health it actually got back
+pub struct HealEvent {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub amount: i16,
+    pub cause: Cause,
+}
+
+/// Sent once when a unit's health drops to 0
+pub struct DeathEvent {
+    pub target: Entity,
+    pub killer: Option<Entity>,
+    pub cause: Cause,
+}
+
+#[derive(Default)]
+struct State {
+    damage_request_reader: EventReader<DamageRequest>,
+    heal_request_reader: EventReader<HealRequest>,
+}
+
+fn resolve_damage(
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    damage_requests: Res<Events<DamageRequest>>,
+    mut damage_events: ResMut<Events<DamageEvent>>,
+    mut death_events: ResMut<Events<DeathEvent>>,
+    mut query: Query<(
+        &mut Health,
+        Option<&Armor>,
+        Option<&Resistances>,
+        Option<&mut StatusEffects>,
+    )>,
 ) {
-    for (_dead, entity, option_selectable, option_abilities) in query.iter() {
-        // If it's a selectable, despawn it's circle too
-        if let Some(selectable) = option_selectable {
-            commands.despawn(selectable.circle);
-        }
+    for request in state.damage_request_reader.iter(&damage_requests) {
+        if let Ok((mut health, armor, resistances, effects)) = query.get_mut(request.target) {
+            // Already dead, probably from another hit this frame
+            if health.value <= 0 {
+                continue;
+            }
+
+            let mut amount = mitigate_damage(request.damage, armor, resistances);
+            if let Some(mut effects) = effects {
+                amount = effects.absorb(amount);
+                if let Some(effect) = request.on_hit {
+                    effects.apply(effect, request.source, time.seconds_since_startup);
+                }
+            }
+            if amount <= 0 {
+                continue;
+            }
+
+            health.damage(amount);
+            damage_events.send(DamageEvent {
+                source: request.source,
+                target: request.target,
+                amount,
+                cause: request.cause,
+            });
 
-        if let Some(abilities) = option_abilities {
-            for ability in &abilities.abilities {
-                let _ = buttons.remove_button(format!("{}{:?}", ability.id, entity));
+            if health.value <= 0 {
+                death_events.send(DeathEvent {
+                    target: request.target,
+                    killer: request.source,
+                    cause: request.cause,
+                });
             }
         }
+    }
+}
+
+fn resolve_heals(
+    mut state: ResMut<State>,
+    heal_requests: Res<Events<HealRequest>>,
+    mut heal_events: ResMut<Events<HealEvent>>,
+    mut query: Query<&mut Health>,
+) {
+    for request in state.heal_request_reader.iter(&heal_requests) {
+        if let Ok(mut health) = query.get_mut(request.target) {
+            if health.value <= 0 {
+                continue;
+            }
 
-        commands.despawn(entity);
+            let amount = health.heal(request.amount);
+            if amount > 0 {
+                heal_events.send(HealEvent {
+                    source: request.source,
+                    target: request.target,
+                    amount,
+                    cause: request.cause,
+                });
+            }
+        }
     }
 }
 
 pub struct HealthPlugin;
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(kill_if_health_0).add_system(remove_if_dead);
+        app.add_event::<DamageRequest>()
+            .add_event::<HealRequest>()
+            .add_event::<DamageEvent>()
+            .add_event::<HealEvent>()
+            .add_event::<DeathEvent>()
+            .init_resource::<State>()
+            .add_system(resolve_damage)
+            .add_system(resolve_heals);
     }
 }
@@ -10,24 +10,42 @@ struct HealthDifferenceNumber {
 const TEXT_LIFETIME: f64 = 0.5;
 const TEXT_SPEED: f32 = 10.;
 
+#[derive(Default)]
+struct State {
+    damage_event_reader: EventReader<DamageEvent>,
+    heal_event_reader: EventReader<HealEvent>,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_health_numbers(
     commands: &mut Commands,
+    mut state: ResMut<State>,
     time: Res<ControlledTime>,
     assets: Res<UiAssetsResource>,
     fonts: Res<Assets<Font>>,
+    damage_events: Res<Events<DamageEvent>>,
+    heal_events: Res<Events<HealEvent>>,
     mut textures: ResMut<Assets<Texture>>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
-    query: Query<(&Health, &Transform), Mutated<Health>>,
+    query: Query<(&Health, &Transform)>,
 ) {
+    // Damage shows up as a negative difference, and healing as a positive one
+    let mut differences = Vec::new();
+    for event in state.damage_event_reader.iter(&damage_events) {
+        differences.push((event.target, -event.amount));
+    }
+    for event in state.heal_event_reader.iter(&heal_events) {
+        differences.push((event.target, event.amount));
+    }
+
     if let Some(font) = fonts.get(assets.font.clone()) {
         let mut rng = rand::thread_rng();
 
-        for (health, transform) in query.iter() {
-            let diff = health.difference();
-
-            if diff == 0 {
-                continue;
-            }
+        for (target, diff) in differences {
+            let transform = match query.get_component::<Transform>(target) {
+                Ok(transform) => transform,
+                Err(_) => continue,
+            };
 
             let text = font.render_text(
                 &*format!("{}", diff.abs()),
@@ -100,7 +118,8 @@ fn despawn_numbers(
 pub struct HealthNumbersPlugin;
 impl Plugin for HealthNumbersPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(spawn_health_numbers)
+        app.init_resource::<State>()
+            .add_system(spawn_health_numbers)
             .add_system(move_numbers_up_and_rotate)
             .add_system(despawn_numbers);
     }
@@ -0,0 +1,73 @@
+use crate::helpers::collision::*;
+use crate::systems::faction::*;
+use bevy::prelude::*;
+
+pub struct LineOfSightSettings {
+    /// If true, ranged units won't fire when one of their allies is in the way
+    pub allies_block_shots: bool,
+}
+impl Default for LineOfSightSettings {
+    fn default() -> Self {
+        Self {
+            allies_block_shots: true,
+        }
+    }
+}
+
+/// Anything that isn't a unit but still blocks shots, e.g. terrain
+pub struct LineOfSightBlocker {
+    pub radius: f32,
+}
+
+/// Something that could be standing between a shooter and its target
+pub struct Obstruction {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub radius: f32,
+    /// `None` for obstructions that block everyone, like terrain
+    pub faction: Option<FactionId>,
+}
+
+/// Checks if there's nothing blocking the way from `from` to `to`.
+/// `ignore` should contain at least the shooter and the target
+pub fn has_line_of_sight(
+    from: Vec3,
+    to: Vec3,
+    ignore: &[Entity],
+    shooter_faction: FactionId,
+    obstructions: &[Obstruction],
+    factions: &Factions,
+    settings: &LineOfSightSettings,
+) -> bool {
+    for obstruction in obstructions {
+        if ignore.contains(&obstruction.entity) {
+            continue;
+        }
+
+        match obstruction.faction {
+            Some(faction) if factions.is_allied(faction, shooter_faction) => {
+                if !settings.allies_block_shots {
+                    continue;
+                }
+            }
+            // Anyone else in the way will just take the hit instead
+            Some(_) => continue,
+            None => {}
+        }
+
+        // Units that are touching the shooter or the target don't block the shot
+        let radius_squared = obstruction.radius * obstruction.radius;
+        if (from - obstruction.position).length_squared() <= radius_squared
+            || (to - obstruction.position).length_squared() <= radius_squared
+        {
+            continue;
+        }
+
+        if segment_sphere_intersection(from, to, obstruction.position, obstruction.radius).is_some()
+        {
+            return false;
+        }
+    }
+
+    true
+}
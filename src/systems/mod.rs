@@ -1,19 +1,30 @@
 pub mod ability;
 pub mod aliens;
 pub mod attack;
+pub mod aura;
 pub mod axes;
+pub mod beam;
 pub mod bullet;
 pub mod camera;
+pub mod casting;
+pub mod death;
 pub mod debug;
 pub mod drag_selection;
 pub mod drone;
 pub mod faction;
+pub mod healer;
 pub mod health;
 pub mod health_numbers;
+pub mod line_of_sight;
+pub mod reticle;
 pub mod selection;
 pub mod selection_circle;
+pub mod stats;
+pub mod status_effect;
+pub mod summon;
 pub mod target_indicator;
 pub mod time;
+pub mod timed_visual;
 pub mod ui;
 pub mod unit;
 pub mod walker;
@@ -0,0 +1,165 @@
+use crate::helpers::shapes::*;
+use crate::systems::{
+    ability::*,
+    death::Dying,
+    faction::{Faction, Factions},
+    selection::Selectable,
+    selection_circle::SelectionCircle,
+    time::ControlledTime,
+};
+use bevy::prelude::*;
+use bevy_mod_picking::*;
+
+/// Shape on the ground showing where an area ability will land
+#[derive(Default)]
+struct Reticle {
+    shape: Option<Targeting>,
+    // Units whose selection circle is highlighted because they're inside the reticle
+    highlighted: Vec<Entity>,
+}
+
+fn create_reticle(
+    commands: &mut Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn(SpriteBundle {
+            material: color_materials.add(Color::rgba(0.1, 0.8, 0.2, 0.25).into()),
+            mesh: meshes.add(sector_mesh()),
+            sprite: Sprite {
+                size: Vec2::new(1.0, 1.0),
+                ..Default::default()
+            },
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with(Reticle::default());
+}
+
+/// Moves the reticle under the cursor while an area ability is being targeted, and highlights
+/// the units it would affect
+#[allow(clippy::too_many_arguments)]
+fn update_reticle(
+    time: Res<ControlledTime>,
+    ability: Res<CurrentAbility>,
+    registry: Res<AbilityRegistry>,
+    factions: Res<Factions>,
+    pick_state: Res<PickState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut reticle_query: Query<(&mut Reticle, &Handle<Mesh>, &mut Transform, &mut Draw)>,
+    units_query: Query<(&Selectable, &Transform, &Faction), Without<Dying>>,
+    caster_query: Query<
+        (
+            &Selectable,
+            &Transform,
+            &Faction,
+            &UnitAbilities,
+            Option<&Energy>,
+            Entity,
+        ),
+        Without<Dying>,
+    >,
+    mut circle_query: Query<&mut SelectionCircle>,
+) {
+    let cursor = pick_state
+        .top(Group::default())
+        .map(|(_, intersection)| *intersection.position());
+
+    // Area ability being targeted, with the caster that would cast it
+    let area = match ability.ability {
+        Ability::Cast { ability } => registry
+            .get(ability)
+            .filter(|definition| {
+                matches!(
+                    definition.targeting,
+                    Targeting::Area { .. } | Targeting::Cone { .. }
+                )
+            })
+            .and_then(|definition| {
+                let casters =
+                    ready_casters(definition, time.seconds_since_startup, caster_query.iter());
+                best_caster(&casters, cursor)
+                    .map(|caster| (definition, caster.position, caster.faction))
+            }),
+        _ => None,
+    };
+
+    for (mut reticle, mesh_handle, mut transform, mut draw) in reticle_query.iter_mut() {
+        let mut highlighted = Vec::new();
+
+        match (area, cursor) {
+            (Some((definition, caster_position, caster_faction)), Some(cursor)) => {
+                // Rebuild the mesh when switching to an ability with a different shape
+                if reticle.shape != Some(definition.targeting) {
+                    if let Some(mesh) = meshes.get_mut(mesh_handle) {
+                        match definition.targeting {
+                            Targeting::Cone { angle, .. } => set_sector_attributes(mesh, angle),
+                            _ => set_sector_attributes(mesh, std::f32::consts::PI * 2.),
+                        }
+                    }
+                    reticle.shape = Some(definition.targeting);
+                }
+
+                let ground = |position: Vec3| Vec3::new(position.x, 0.15, position.z);
+                *transform = match definition.targeting {
+                    Targeting::Cone { length, .. } => {
+                        let mut transform = Transform::from_translation(ground(caster_position));
+                        transform.look_at(ground(cursor), Vec3::unit_y());
+                        transform.scale = Vec3::splat(length);
+                        transform
+                    }
+                    Targeting::Area { radius } => Transform {
+                        translation: ground(cursor),
+                        scale: Vec3::splat(radius),
+                        ..Default::default()
+                    },
+                    _ => *transform,
+                };
+                draw.is_visible = true;
+
+                for (selectable, unit_transform, faction) in units_query.iter() {
+                    if definition.targeting.covers(
+                        caster_position,
+                        cursor,
+                        unit_transform.translation,
+                    ) && definition
+                        .affects
+                        .includes(&factions, caster_faction, faction.faction)
+                    {
+                        highlighted.push(selectable.circle);
+                    }
+                }
+            }
+            _ => {
+                draw.is_visible = false;
+            }
+        }
+
+        // Only touch the circles that changed, so that button hovers keep working
+        for circle in &reticle.highlighted {
+            if !highlighted.contains(circle) {
+                if let Ok(mut circle) = circle_query.get_mut(*circle) {
+                    circle.unit_highlighted = false;
+                }
+            }
+        }
+        for circle in &highlighted {
+            if let Ok(mut circle) = circle_query.get_mut(*circle) {
+                circle.unit_highlighted = true;
+            }
+        }
+        reticle.highlighted = highlighted;
+    }
+}
+
+pub struct ReticlePlugin;
+impl Plugin for ReticlePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(create_reticle)
+            .add_system(update_reticle);
+    }
+}
@@ -1,7 +1,10 @@
 use crate::systems::{
     ability::*,
+    attack::Ranged,
+    death::Dying,
+    faction::{Faction, Factions},
     selection_circle::*,
-    unit::{TargetPosition, UnitSize},
+    unit::{TargetPosition, Unit, UnitSize},
 };
 use bevy::prelude::*;
 use bevy_mod_picking::*;
@@ -70,7 +73,7 @@ fn select_units(
     pick_state: Res<PickState>,
     keyboard_input: Res<Input<KeyCode>>,
     mouse_button_inputs: Res<Input<MouseButton>>,
-    mut query: Query<&mut Selectable>,
+    mut query: Query<&mut Selectable, Without<Dying>>,
 ) {
     if ability.ability != Ability::Select {
         return;
@@ -97,10 +100,20 @@ fn select_units(
 }
 
 fn set_target_for_selected(
+    commands: &mut Commands,
     pick_state: Res<PickState>,
     mouse_button_inputs: Res<Input<MouseButton>>,
     ability: Res<CurrentAbility>,
-    mut query: Query<(&Selectable, &mut TargetPosition)>,
+    factions: Res<Factions>,
+    mut query: Query<(
+        &Selectable,
+        &mut TargetPosition,
+        &Faction,
+        Option<&mut Ranged>,
+        Option<&PendingCast>,
+        Entity,
+    )>,
+    units_query: Query<(&Unit, &Faction), Without<Dying>>,
 ) {
     if ability.ability != Ability::Select {
         return;
@@ -108,12 +121,39 @@ fn set_target_for_selected(
 
     if mouse_button_inputs.just_pressed(MouseButton::Right) {
         // Get the world position
-        if let Some((_top_entity, intersection)) = pick_state.top(Group::default()) {
+        if let Some((top_entity, intersection)) = pick_state.top(Group::default()) {
             let pos = intersection.position();
+            let clicked_faction = units_query
+                .get_component::<Faction>(*top_entity)
+                .ok()
+                .map(|faction| faction.faction);
+
+            for (selectable, mut target, faction, ranged, pending_cast, entity) in query.iter_mut()
+            {
+                if !selectable.selected {
+                    continue;
+                }
+
+                // A new order cancels any cast the unit was walking to
+                if pending_cast.is_some() {
+                    commands.remove_one::<PendingCast>(entity);
+                }
+
+                match (clicked_faction, ranged) {
+                    // Right clicking an enemy focuses fire on it
+                    (Some(clicked_faction), Some(mut ranged))
+                        if factions.is_hostile(faction.faction, clicked_faction) =>
+                    {
+                        ranged.focus_target = Some(*top_entity);
+                    }
+                    (_, ranged) => {
+                        target.update_to_vec(pos);
 
-            for (selectable, mut target) in query.iter_mut() {
-                if selectable.selected {
-                    target.update_to_vec(pos);
+                        // Moving cancels the focus
+                        if let Some(mut ranged) = ranged {
+                            ranged.focus_target = None;
+                        }
+                    }
                 }
             }
         }
@@ -0,0 +1,118 @@
+use crate::systems::{camera::CameraFollow, faction::*, health::*, ui::UiAssetsResource};
+use bevy::prelude::*;
+
+/// Running totals for a unit, built from the damage, heal and death events
+#[derive(Default)]
+pub struct CombatStats {
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub healing_done: i32,
+    pub kills: u32,
+}
+
+#[derive(Default)]
+struct State {
+    damage_event_reader: EventReader<DamageEvent>,
+    heal_event_reader: EventReader<HealEvent>,
+    death_event_reader: EventReader<DeathEvent>,
+}
+
+fn track_combat_stats(
+    mut state: ResMut<State>,
+    damage_events: Res<Events<DamageEvent>>,
+    heal_events: Res<Events<HealEvent>>,
+    death_events: Res<Events<DeathEvent>>,
+    mut query: Query<&mut CombatStats>,
+) {
+    for event in state.damage_event_reader.iter(&damage_events) {
+        if let Some(source) = event.source {
+            if let Ok(mut stats) = query.get_mut(source) {
+                stats.damage_dealt += event.amount as i32;
+            }
+        }
+        if let Ok(mut stats) = query.get_mut(event.target) {
+            stats.damage_taken += event.amount as i32;
+        }
+    }
+
+    for event in state.heal_event_reader.iter(&heal_events) {
+        if let Some(source) = event.source {
+            if let Ok(mut stats) = query.get_mut(source) {
+                stats.healing_done += event.amount as i32;
+            }
+        }
+    }
+
+    for event in state.death_event_reader.iter(&death_events) {
+        if let Some(killer) = event.killer {
+            if let Ok(mut stats) = query.get_mut(killer) {
+                stats.kills += 1;
+            }
+        }
+    }
+}
+
+// Shows the stats of the unit that has the camera
+struct StatsText;
+fn init_stats_text(commands: &mut Commands, assets: Res<UiAssetsResource>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(60.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                value: String::new(),
+                font: assets.font.clone(),
+                style: TextStyle {
+                    font_size: 25.0,
+                    color: Color::rgb(0.8, 0.8, 0.8),
+                    ..Default::default()
+                },
+            },
+            ..Default::default()
+        })
+        .with(StatsText);
+}
+
+fn stats_text_update(
+    factions: Res<Factions>,
+    camera_query: Query<&CameraFollow>,
+    stats_query: Query<(&CombatStats, &Faction)>,
+    mut text_query: Query<(&mut Text, &StatsText)>,
+) {
+    let stats = camera_query
+        .iter()
+        .next()
+        .and_then(|camera_follow| camera_follow.entity)
+        .and_then(|entity| stats_query.get(entity).ok());
+
+    for (mut text, _tag) in text_query.iter_mut() {
+        text.value = match stats {
+            Some((stats, faction)) => format!(
+                "{}  Kills: {}  Dealt: {}  Taken: {}  Healed: {}",
+                factions.name(faction.faction),
+                stats.kills,
+                stats.damage_dealt,
+                stats.damage_taken,
+                stats.healing_done
+            ),
+            None => String::new(),
+        };
+    }
+}
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<State>()
+            .add_startup_system(init_stats_text)
+            .add_system(track_combat_stats)
+            .add_system(stats_text_update);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::helpers::billboard::BillboardCamera;
+use crate::systems::{camera::CameraFollow, health::*, time::*, unit::UnitSize};
+use bevy::prelude::*;
+
+const ICON_SIZE: f32 = 0.4;
+const ICON_SPACING: f32 = 0.5;
+// Shortest interval between two ticks of an effect or a channel
+pub const MIN_TICK_INTERVAL: f32 = 0.1;
+
+/// Unit stats that status effects can modify, without touching the base values
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stat {
+    Speed,
+    Range,
+    AttackRate,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EffectKind {
+    /// Multiplies the unit's speed
+    Slow { multiplier: f32 },
+    /// Multiplies one of the unit's stats. Modifiers on the same stat multiply each other
+    Modifier { stat: Stat, multiplier: f32 },
+    /// Can't move or attack
+    Stun,
+    /// Deals `damage` every `interval` seconds. Intervals shorter than `MIN_TICK_INTERVAL` are
+    /// clamped to it
+    DamageOverTime { damage: Damage, interval: f32 },
+    /// Heals `amount` every `interval` seconds. Intervals shorter than `MIN_TICK_INTERVAL` are
+    /// clamped to it
+    HealOverTime { amount: i16, interval: f32 },
+    /// Absorbs incoming damage until it runs out
+    Shield { amount: i16 },
+}
+
+/// What happens when an effect is applied to a unit that already has it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stacking {
+    /// Resets the duration of the existing effect, keeping its ticks on schedule
+    Refresh,
+    /// Adds a new independent stack, up to `max_stacks`. Once full, the oldest one is replaced
+    Stack { max_stacks: usize },
+    /// The existing effect is left untouched
+    Ignore,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StatusEffect {
+    /// Effects with the same id stack with each other
+    pub id: &'static str,
+    pub kind: EffectKind,
+    /// Seconds the effect lasts for
+    pub duration: f32,
+    pub stacking: Stacking,
+}
+
+struct ActiveEffect {
+    effect: StatusEffect,
+    source: Option<Entity>,
+    applied_at: f64,
+    expires_at: f64,
+    next_tick_at: f64,
+    // Damage the effect can still absorb, for shields
+    shield_left: i16,
+}
+
+#[derive(Default)]
+pub struct StatusEffects {
+    effects: Vec<ActiveEffect>,
+    icons: Vec<Entity>,
+    icons_dirty: bool,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, mut effect: StatusEffect, source: Option<Entity>, current_time: f64) {
+        // An interval of 0 would tick forever in a single frame
+        match &mut effect.kind {
+            EffectKind::DamageOverTime { interval, .. }
+            | EffectKind::HealOverTime { interval, .. } => {
+                *interval = interval.max(MIN_TICK_INTERVAL);
+            }
+            _ => {}
+        }
+
+        let active = ActiveEffect {
+            effect,
+            source,
+            applied_at: current_time,
+            expires_at: current_time + effect.duration as f64,
+            next_tick_at: current_time + effect.kind.interval().unwrap_or(0.) as f64,
+            shield_left: match effect.kind {
+                EffectKind::Shield { amount } => amount,
+                _ => 0,
+            },
+        };
+
+        let stacks = self
+            .effects
+            .iter()
+            .filter(|active| active.effect.id == effect.id)
+            .count();
+        if stacks == 0 {
+            self.effects.push(active);
+            self.icons_dirty = true;
+            return;
+        }
+
+        match effect.stacking {
+            Stacking::Refresh => {
+                if let Some(existing) = self
+                    .effects
+                    .iter_mut()
+                    .find(|existing| existing.effect.id == effect.id)
+                {
+                    let next_tick_at = existing.next_tick_at;
+                    *existing = active;
+                    existing.next_tick_at = next_tick_at;
+                }
+            }
+            Stacking::Stack { max_stacks } => {
+                if stacks >= max_stacks {
+                    // Replace the oldest stack
+                    if let Some(index) = self
+                        .effects
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, existing)| existing.effect.id == effect.id)
+                        .min_by(|(_, a), (_, b)| a.applied_at.partial_cmp(&b.applied_at).unwrap())
+                        .map(|(index, _)| index)
+                    {
+                        self.effects.remove(index);
+                    }
+                }
+                self.effects.push(active);
+            }
+            Stacking::Ignore => {}
+        }
+    }
+
+    /// Multiplier for one of the unit's stats after every buff and debuff
+    pub fn stat_multiplier(&self, stat: Stat) -> f32 {
+        self.effects
+            .iter()
+            .filter_map(|active| match active.effect.kind {
+                EffectKind::Slow { multiplier } if stat == Stat::Speed => Some(multiplier),
+                EffectKind::Modifier {
+                    stat: modified,
+                    multiplier,
+                } if modified == stat => Some(multiplier),
+                _ => None,
+            })
+            .product()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|active| active.effect.kind == EffectKind::Stun)
+    }
+
+    /// Lets shields soak up the damage. Returns the damage that got through
+    pub fn absorb(&mut self, mut amount: i16) -> i16 {
+        for active in self.effects.iter_mut() {
+            if amount <= 0 {
+                break;
+            }
+
+            if let EffectKind::Shield { .. } = active.effect.kind {
+                let absorbed = amount.min(active.shield_left);
+                active.shield_left -= absorbed;
+                amount -= absorbed;
+            }
+        }
+
+        // Broken shields go away
+        let old_len = self.effects.len();
+        self.effects.retain(|active| match active.effect.kind {
+            EffectKind::Shield { .. } => active.shield_left > 0,
+            _ => true,
+        });
+        if old_len != self.effects.len() {
+            self.icons_dirty = true;
+        }
+
+        amount
+    }
+
+    /// Kinds of the effects currently active, one for each effect id
+    fn distinct_kinds(&self) -> Vec<EffectKind> {
+        let mut ids = Vec::new();
+        let mut kinds = Vec::new();
+        for active in &self.effects {
+            if !ids.contains(&active.effect.id) {
+                ids.push(active.effect.id);
+                kinds.push(active.effect.kind);
+            }
+        }
+        kinds
+    }
+}
+
+impl EffectKind {
+    fn interval(&self) -> Option<f32> {
+        match self {
+            EffectKind::DamageOverTime { interval, .. }
+            | EffectKind::HealOverTime { interval, .. } => Some(*interval),
+            _ => None,
+        }
+    }
+}
+
+/// Sent by weapons and abilities to put a status effect on a unit
+pub struct StatusEffectRequest {
+    pub source: Option<Entity>,
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+#[derive(Default)]
+struct State {
+    status_effect_request_reader: EventReader<StatusEffectRequest>,
+}
+
+fn apply_status_effects(
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    requests: Res<Events<StatusEffectRequest>>,
+    mut query: Query<&mut StatusEffects>,
+) {
+    for request in state.status_effect_request_reader.iter(&requests) {
+        if let Ok(mut effects) = query.get_mut(request.target) {
+            effects.apply(request.effect, request.source, time.seconds_since_startup);
+        }
+    }
+}
+
+fn tick_status_effects(
+    time: Res<ControlledTime>,
+    mut damage_requests: ResMut<Events<DamageRequest>>,
+    mut heal_requests: ResMut<Events<HealRequest>>,
+    mut query: Query<(&mut StatusEffects, Entity)>,
+) {
+    let now = time.seconds_since_startup;
+
+    for (mut effects, entity) in query.iter_mut() {
+        for active in effects.effects.iter_mut() {
+            // Ticks that fall after the effect expired don't happen
+            while active.next_tick_at <= now.min(active.expires_at) {
+                match active.effect.kind {
+                    EffectKind::DamageOverTime { damage, interval } => {
+                        damage_requests.send(DamageRequest {
+                            source: active.source,
+                            target: entity,
+                            damage,
+                            cause: Cause::StatusEffect,
+                            on_hit: None,
+                        });
+                        active.next_tick_at += interval as f64;
+                    }
+                    EffectKind::HealOverTime { amount, interval } => {
+                        heal_requests.send(HealRequest {
+                            source: active.source,
+                            target: entity,
+                            amount,
+                            cause: Cause::StatusEffect,
+                        });
+                        active.next_tick_at += interval as f64;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        let old_len = effects.effects.len();
+        effects.effects.retain(|active| active.expires_at > now);
+        if old_len != effects.effects.len() {
+            effects.icons_dirty = true;
+        }
+    }
+}
+
+struct StatusIcon {
+    owner: Entity,
+    index: usize,
+}
+
+fn rebuild_status_icons(
+    commands: &mut Commands,
+    materials: Res<StatusIconMaterials>,
+    mut query: Query<(&mut StatusEffects, Entity)>,
+) {
+    for (mut effects, entity) in query.iter_mut() {
+        if !effects.icons_dirty {
+            continue;
+        }
+        effects.icons_dirty = false;
+
+        for icon in effects.icons.drain(..) {
+            commands.despawn(icon);
+        }
+
+        for (index, kind) in effects.distinct_kinds().into_iter().enumerate() {
+            let icon = commands
+                .spawn(SpriteBundle {
+                    material: materials.for_kind(&kind),
+                    sprite: Sprite {
+                        size: Vec2::new(1.0, 1.0),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_scale(Vec3::splat(ICON_SIZE)),
+                    ..Default::default()
+                })
+                .with(StatusIcon {
+                    owner: entity,
+                    index,
+                })
+                .current_entity()
+                .unwrap();
+            effects.icons.push(icon);
+        }
+    }
+}
+
+/// Lines up the icons of each unit in a row above it
+fn move_status_icons(
+    commands: &mut Commands,
+    mut icon_query: Query<(&StatusIcon, &mut Transform, Entity)>,
+    owner_query: Query<(&StatusEffects, &Transform, &UnitSize)>,
+    camera_query: Query<(&CameraFollow, &Transform)>,
+) {
+    let camera = match BillboardCamera::find(&camera_query) {
+        Some(camera) => camera,
+        None => return,
+    };
+
+    for (icon, mut transform, entity) in icon_query.iter_mut() {
+        let (effects, owner_transform, size) = match owner_query.get(icon.owner) {
+            Ok(owner) => owner,
+            Err(_) => {
+                // The owner is gone
+                commands.despawn(entity);
+                continue;
+            }
+        };
+
+        let count = effects.icons.len() as f32;
+        let offset = (icon.index as f32 - (count - 1.) * 0.5) * ICON_SPACING;
+        camera.place(
+            &mut transform,
+            owner_transform.translation + Vec3::unit_y() * (size.0 + 0.5) + camera.right * offset,
+        );
+    }
+}
+
+struct StatusIconMaterials {
+    slow: Handle<ColorMaterial>,
+    modifier: Handle<ColorMaterial>,
+    stun: Handle<ColorMaterial>,
+    damage_over_time: Handle<ColorMaterial>,
+    heal_over_time: Handle<ColorMaterial>,
+    shield: Handle<ColorMaterial>,
+}
+
+impl StatusIconMaterials {
+    fn for_kind(&self, kind: &EffectKind) -> Handle<ColorMaterial> {
+        match kind {
+            EffectKind::Slow { .. } => self.slow.clone(),
+            EffectKind::Modifier { .. } => self.modifier.clone(),
+            EffectKind::Stun => self.stun.clone(),
+            EffectKind::DamageOverTime { .. } => self.damage_over_time.clone(),
+            EffectKind::HealOverTime { .. } => self.heal_over_time.clone(),
+            EffectKind::Shield { .. } => self.shield.clone(),
+        }
+    }
+}
+
+impl FromResources for StatusIconMaterials {
+    fn from_resources(resources: &Resources) -> Self {
+        let mut materials = resources.get_mut::<Assets<ColorMaterial>>().unwrap();
+        StatusIconMaterials {
+            slow: materials.add(Color::rgb(0.2, 0.4, 1.0).into()),
+            modifier: materials.add(Color::rgb(1.0, 0.5, 0.1).into()),
+            stun: materials.add(Color::rgb(1.0, 0.9, 0.1).into()),
+            damage_over_time: materials.add(Color::rgb(0.9, 0.1, 0.1).into()),
+            heal_over_time: materials.add(Color::rgb(0.1, 0.8, 0.1).into()),
+            shield: materials.add(Color::rgb(0.7, 0.9, 1.0).into()),
+        }
+    }
+}
+
+pub struct StatusEffectPlugin;
+impl Plugin for StatusEffectPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<StatusEffectRequest>()
+            .init_resource::<State>()
+            .init_resource::<StatusIconMaterials>()
+            .add_system(apply_status_effects)
+            .add_system(tick_status_effects)
+            .add_system(rebuild_status_icons)
+            .add_system(move_status_icons);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::prefabs::*;
+use crate::systems::{
+    ability::AbilityId,
+    death::Dying,
+    faction::Faction,
+    health::{Cause, DeathEvent, Health},
+    time::*,
+};
+use bevy::prelude::*;
+
+/// Units created by a summoning ability
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Summon {
+    pub prefab: Prefab,
+    pub lifetime: Option<f32>, // Seconds before the summoned unit goes away
+    pub cap: usize,            // Most units a caster can have summoned with the ability at once
+}
+
+/// Sent by abilities to summon a unit on the summoner's side
+pub struct SummonRequest {
+    pub summoner: Entity,
+    pub ability: AbilityId,
+    pub summon: Summon,
+    pub position: Vec3,
+}
+
+/// Unit that was summoned by another one
+pub struct Summoned {
+    pub summoner: Entity,
+    pub ability: AbilityId,
+    summoned_at: f64,
+    expires_at: Option<f64>,
+    expired: bool,
+}
+
+/// Kills the summoned unit, which then goes through its death sequence like any other.
+/// Units that already got killed this frame keep the death they had
+fn expire(
+    summoned: &mut Summoned,
+    health: &mut Health,
+    entity: Entity,
+    death_events: &mut Events<DeathEvent>,
+) {
+    summoned.expired = true;
+    if health.value <= 0 {
+        return;
+    }
+    health.value = 0;
+    death_events.send(DeathEvent {
+        target: entity,
+        killer: None,
+        cause: Cause::Expired,
+    });
+}
+
+#[derive(Default)]
+struct State {
+    summon_request_reader: EventReader<SummonRequest>,
+}
+
+fn summon_units(
+    commands: &mut Commands,
+    mut state: ResMut<State>,
+    time: Res<ControlledTime>,
+    prefabs: Res<PrefabResource>,
+    summon_requests: Res<Events<SummonRequest>>,
+    mut death_events: ResMut<Events<DeathEvent>>,
+    summoner_query: Query<&Faction>,
+    mut summoned_query: Query<(&mut Summoned, &mut Health, Entity), Without<Dying>>,
+) {
+    for request in state.summon_request_reader.iter(&summon_requests) {
+        // There's never room for a unit the ability can't have any of
+        if request.summon.cap == 0 {
+            continue;
+        }
+
+        let faction = match summoner_query.get(request.summoner) {
+            Ok(faction) => faction.faction,
+            Err(_) => continue,
+        };
+
+        // Make room by getting rid of the oldest units summoned with the same ability
+        let mut previous: Vec<(f64, Entity)> = summoned_query
+            .iter_mut()
+            .filter(|(summoned, _, _)| {
+                summoned.summoner == request.summoner
+                    && summoned.ability == request.ability
+                    && !summoned.expired
+            })
+            .map(|(summoned, _, entity)| (summoned.summoned_at, entity))
+            .collect();
+        previous.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        let excess = (previous.len() + 1).saturating_sub(request.summon.cap);
+        for (_, entity) in previous.iter().take(excess) {
+            if let Ok((mut summoned, mut health, _)) = summoned_query.get_mut(*entity) {
+                expire(&mut summoned, &mut health, *entity, &mut death_events);
+            }
+        }
+
+        let entity = request
+            .summon
+            .prefab
+            .spawn(commands, &prefabs, request.position, faction);
+        commands.insert_one(
+            entity,
+            Summoned {
+                summoner: request.summoner,
+                ability: request.ability,
+                summoned_at: time.seconds_since_startup,
+                expires_at: request
+                    .summon
+                    .lifetime
+                    .map(|lifetime| time.seconds_since_startup + lifetime as f64),
+                expired: false,
+            },
+        );
+    }
+}
+
+fn expire_summons(
+    time: Res<ControlledTime>,
+    mut death_events: ResMut<Events<DeathEvent>>,
+    mut query: Query<(&mut Summoned, &mut Health, Entity), Without<Dying>>,
+) {
+    for (mut summoned, mut health, entity) in query.iter_mut() {
+        if summoned.expired {
+            continue;
+        }
+
+        if let Some(expires_at) = summoned.expires_at {
+            if time.seconds_since_startup >= expires_at {
+                expire(&mut summoned, &mut health, entity, &mut death_events);
+            }
+        }
+    }
+}
+
+pub struct SummonPlugin;
+impl Plugin for SummonPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<State>()
+            .add_event::<SummonRequest>()
+            .add_system(summon_units)
+            .add_system(expire_summons);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::systems::time::*;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+/// Short-lived meshes, like explosions and beams, shown for a moment wherever they're needed.
+/// Faded visuals are hidden and kept around to be shown again, instead of spawning and
+/// despawning an entity every time. `T` only tells the pools apart
+pub struct TimedVisualPool<T> {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    lifetime: f64, // Seconds a visual stays visible
+    size: usize,   // Visuals created up front
+    free: Vec<Entity>,
+    queued: Vec<Transform>,
+    marker: PhantomData<T>,
+}
+
+impl<T> TimedVisualPool<T> {
+    pub fn new(
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+        lifetime: f64,
+        size: usize,
+    ) -> Self {
+        Self {
+            mesh,
+            material,
+            lifetime,
+            size,
+            free: Vec::with_capacity(size),
+            queued: Vec::with_capacity(size),
+            marker: PhantomData,
+        }
+    }
+
+    /// Queues a visual to be shown this frame
+    pub fn show(&mut self, transform: Transform) {
+        self.queued.push(transform);
+    }
+
+    fn bundle(&self, transform: Transform, is_visible: bool) -> PbrBundle {
+        PbrBundle {
+            mesh: self.mesh.clone(),
+            material: self.material.clone(),
+            transform,
+            draw: Draw {
+                is_visible,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+struct TimedVisual<T> {
+    active: bool,
+    should_hide_at: f64,
+    marker: PhantomData<T>,
+}
+
+impl<T> TimedVisual<T> {
+    fn new(active: bool, should_hide_at: f64) -> Self {
+        Self {
+            active,
+            should_hide_at,
+            marker: PhantomData,
+        }
+    }
+}
+
+fn fill_pool<T: Send + Sync + 'static>(
+    commands: &mut Commands,
+    mut pool: ResMut<TimedVisualPool<T>>,
+) {
+    for _ in 0..pool.size {
+        let entity = commands
+            .spawn(pool.bundle(Transform::default(), false))
+            .with(TimedVisual::<T>::new(false, 0.))
+            .current_entity()
+            .unwrap();
+        pool.free.push(entity);
+    }
+}
+
+fn show_visuals<T: Send + Sync + 'static>(
+    commands: &mut Commands,
+    time: Res<ControlledTime>,
+    mut pool: ResMut<TimedVisualPool<T>>,
+    mut query: Query<(&mut TimedVisual<T>, &mut Transform, &mut Draw)>,
+) {
+    let should_hide_at = time.seconds_since_startup + pool.lifetime;
+    let queued = std::mem::take(&mut pool.queued);
+
+    for transform in queued {
+        if let Some(entity) = pool.free.pop() {
+            if let Ok((mut visual, mut pooled_transform, mut draw)) = query.get_mut(entity) {
+                *visual = TimedVisual::new(true, should_hide_at);
+                *pooled_transform = transform;
+                draw.is_visible = true;
+                continue;
+            }
+        }
+
+        // The pool ran dry, so it grows with a visual that gets hidden into it later
+        commands
+            .spawn(pool.bundle(transform, true))
+            .with(TimedVisual::<T>::new(true, should_hide_at));
+    }
+}
+
+fn hide_visuals<T: Send + Sync + 'static>(
+    time: Res<ControlledTime>,
+    mut pool: ResMut<TimedVisualPool<T>>,
+    mut query: Query<(&mut TimedVisual<T>, &mut Draw, Entity)>,
+) {
+    for (mut visual, mut draw, entity) in query.iter_mut() {
+        if visual.active && time.seconds_since_startup >= visual.should_hide_at {
+            visual.active = false;
+            draw.is_visible = false;
+            pool.free.push(entity);
+        }
+    }
+}
+
+/// Sets up the `TimedVisualPool<T>`, created from the resources
+pub struct TimedVisualPlugin<T>(PhantomData<T>);
+impl<T> Default for TimedVisualPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for TimedVisualPlugin<T>
+where
+    TimedVisualPool<T>: FromResources,
+{
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<TimedVisualPool<T>>()
+            .add_startup_system(fill_pool::<T>)
+            .add_system(show_visuals::<T>)
+            .add_system(hide_visuals::<T>);
+    }
+}
@@ -1,12 +1,19 @@
 use crate::systems::{
-    ability::{Ability, CurrentAbility},
+    ability::{
+        group_cast_state, Ability, AbilityId, AbilityRegistry, CastState, CurrentAbility, Energy,
+        UnitAbilities,
+    },
+    selection::Selectable,
     selection_circle::*,
+    time::ControlledTime,
 };
-use bevy::prelude::*;
+use bevy::{ecs::Command, prelude::*};
 use bevy_mod_picking::*;
+use std::sync::Arc;
 
 pub struct UiAssetsResource {
     material: Handle<ColorMaterial>,
+    material_disabled: Handle<ColorMaterial>,
     material_none: Handle<ColorMaterial>,
     pub font: Handle<Font>,
 }
@@ -18,22 +25,24 @@ impl FromResources for UiAssetsResource {
         UiAssetsResource {
             font: asset_server.load("fonts/FiraSans-Bold.ttf"),
             material: color_materials.add(Color::rgb(0.02, 0.02, 0.02).into()),
+            material_disabled: color_materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
             material_none: color_materials.add(Color::NONE.into()),
         }
     }
 }
 
 pub type ButtonIdentifier = String;
+/// Name, identifier, callback, callback for right clicks, and the data passed to the callbacks
 pub type ButtonTuple = (
     String,
     ButtonIdentifier,
-    AbilityChangeCallback,
+    ButtonCallback,
+    Option<ButtonCallback>,
     CallbackData,
 );
 #[derive(Default, Clone, Copy)]
 pub struct CallbackData {
-    pub entity: Option<Entity>,
-    pub associated_circle: Option<Entity>,
+    pub ability: Option<AbilityId>,
 }
 
 pub struct AvailableButtons {
@@ -46,7 +55,7 @@ impl AvailableButtons {
         let identifier = button.1.clone();
 
         // Check that there aren't any buttons with that identifier
-        for (_, id, _, _) in &self.buttons {
+        for (_, id, _, _, _) in &self.buttons {
             if *id == identifier {
                 return Err("Already used ID");
             }
@@ -62,7 +71,7 @@ impl AvailableButtons {
         let old_len = self.buttons.len();
 
         // Remove buttons with identifier
-        self.buttons.retain(|(_, id, _, _)| *id != identifier);
+        self.buttons.retain(|(_, id, _, _, _)| *id != identifier);
 
         let new_len = self.buttons.len();
 
@@ -80,17 +89,21 @@ impl FromResources for AvailableButtons {
                 (
                     "Switch Camera".to_string(),
                     "switch_camera".to_string(),
-                    |_, mut ability, _, _| {
-                        ability.ability = Ability::SwitchCamera;
-                    },
+                    button_callback(|_, resources, _| {
+                        resources.get_mut::<CurrentAbility>().unwrap().ability =
+                            Ability::SwitchCamera;
+                    }),
+                    None,
                     CallbackData::default(),
                 ),
                 (
                     "Switch back".to_string(),
                     "switch_back_camera".to_string(),
-                    |_, mut ability, _, _| {
-                        ability.ability = Ability::SwitchBack;
-                    },
+                    button_callback(|_, resources, _| {
+                        resources.get_mut::<CurrentAbility>().unwrap().ability =
+                            Ability::SwitchBack;
+                    }),
+                    None,
                     CallbackData::default(),
                 ),
             ],
@@ -98,6 +111,29 @@ impl FromResources for AvailableButtons {
     }
 }
 
+/// Keys bound to the ability slots of the command card, in order, and the key that cancels
+/// the ability being targeted
+pub struct Hotkeys {
+    pub slots: Vec<KeyCode>,
+    pub cancel: KeyCode,
+}
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            slots: vec![
+                // Letters are taken by the unit and camera movement
+                KeyCode::Key1,
+                KeyCode::Key2,
+                KeyCode::Key3,
+                KeyCode::Key4,
+                KeyCode::Key5,
+                KeyCode::Key6,
+            ],
+            cancel: KeyCode::Escape,
+        }
+    }
+}
+
 #[derive(Default)]
 struct DisplayedButtons {
     entities: Vec<Entity>,
@@ -106,6 +142,7 @@ struct DisplayedButtons {
 fn change_displayed_buttons(
     commands: &mut Commands,
     assets: Res<UiAssetsResource>,
+    hotkeys: Res<Hotkeys>,
     mut available_buttons: ResMut<AvailableButtons>,
     mut displayed_buttons: ResMut<DisplayedButtons>,
 ) {
@@ -141,7 +178,25 @@ fn change_displayed_buttons(
             ..Default::default()
         })
         .with_children(|parent| {
-            for (string, _id, callback, callback_data) in &available_buttons.buttons {
+            // Ability buttons take the hotkeys in order
+            let mut slot_hotkeys = hotkeys.slots.iter();
+            for (string, _id, callback, secondary_callback, callback_data) in
+                &available_buttons.buttons
+            {
+                let hotkey = match callback_data.ability {
+                    Some(_) => slot_hotkeys.next().copied(),
+                    None => None,
+                };
+                let name = match hotkey {
+                    // Number keys are debug-printed as "Key1"
+                    Some(key) => format!(
+                        "[{}] {}",
+                        format!("{:?}", key).trim_start_matches("Key"),
+                        string
+                    ),
+                    None => string.clone(),
+                };
+
                 // Spawn a new button
                 parent
                     .spawn(ButtonBundle {
@@ -158,14 +213,18 @@ fn change_displayed_buttons(
                     })
                     .with(PickingBlocker {})
                     .with(AbilityButton {
-                        callback: *callback,
+                        name: name.clone(),
+                        callback: callback.clone(),
+                        secondary_callback: secondary_callback.clone(),
                         data: *callback_data,
+                        disabled: false,
+                        hotkey,
                     })
                     .with_children(|parent| {
                         parent
                             .spawn(TextBundle {
                                 text: Text {
-                                    value: string.clone(),
+                                    value: name,
                                     font: assets.font.clone(),
                                     style: TextStyle {
                                         font_size: 20.0,
@@ -183,36 +242,176 @@ fn change_displayed_buttons(
         .for_current_entity(|entity| displayed_buttons.entities.push(entity));
 }
 
-pub type AbilityChangeCallback =
-    fn(&mut Commands, ResMut<CurrentAbility>, ResMut<AvailableButtons>, CallbackData);
+/// What a button does when pressed. Callbacks can capture their own configuration, and run
+/// with access to the whole world at the end of the stage
+pub type ButtonCallback = Arc<dyn Fn(&mut World, &mut Resources, CallbackData) + Send + Sync>;
+
+pub fn button_callback(
+    callback: impl Fn(&mut World, &mut Resources, CallbackData) + Send + Sync + 'static,
+) -> ButtonCallback {
+    Arc::new(callback)
+}
+
+struct RunButtonCallback {
+    callback: ButtonCallback,
+    data: CallbackData,
+}
+
+impl Command for RunButtonCallback {
+    fn write(self: Box<Self>, world: &mut World, resources: &mut Resources) {
+        (self.callback)(world, resources, self.data);
+    }
+}
+
+fn press_button(commands: &mut Commands, callback: &ButtonCallback, data: CallbackData) {
+    commands.add_command(RunButtonCallback {
+        callback: callback.clone(),
+        data,
+    });
+}
 
 struct AbilityButton {
-    callback: AbilityChangeCallback,
+    name: String,
+    callback: ButtonCallback,
+    secondary_callback: Option<ButtonCallback>, // Run on right clicks
     data: CallbackData,
+    disabled: bool, // Abilities that can't be cast ignore clicks
+    hotkey: Option<KeyCode>,
 }
+
+/// Presses clicked buttons. Hovering an ability button highlights the selected units that
+/// have the ability
 fn button_system(
     commands: &mut Commands,
-    ability: ResMut<CurrentAbility>,
-    available_buttons: ResMut<AvailableButtons>,
     mut interaction_query: Query<(&mut AbilityButton, &Interaction), Mutated<Interaction>>,
     mut circle_query: Query<&mut SelectionCircle>,
+    caster_query: Query<(&Selectable, &UnitAbilities)>,
 ) {
     for (ability_button, interaction) in interaction_query.iter_mut() {
-        if *interaction == Interaction::Clicked {
-            (ability_button.callback)(commands, ability, available_buttons, ability_button.data);
+        if *interaction == Interaction::Clicked && !ability_button.disabled {
+            press_button(commands, &ability_button.callback, ability_button.data);
             return;
         }
 
-        if let Some(associated_entity) = ability_button.data.associated_circle {
-            if let Ok(mut circle) =
-                circle_query.get_component_mut::<SelectionCircle>(associated_entity)
-            {
-                circle.unit_highlighted = *interaction == Interaction::Hovered;
+        if let Some(id) = ability_button.data.ability {
+            for (selectable, abilities) in caster_query.iter() {
+                if !selectable.selected || !abilities.abilities.contains(&id) {
+                    continue;
+                }
+                if let Ok(mut circle) = circle_query.get_mut(selectable.circle) {
+                    circle.unit_highlighted = *interaction == Interaction::Hovered;
+                }
+            }
+        }
+    }
+}
+
+/// Runs the secondary callback of buttons right clicked by the player
+fn secondary_button_system(
+    commands: &mut Commands,
+    mouse_button_inputs: Res<Input<MouseButton>>,
+    button_query: Query<(&AbilityButton, &Interaction)>,
+) {
+    if !mouse_button_inputs.just_pressed(MouseButton::Right) {
+        return;
+    }
+
+    for (ability_button, interaction) in button_query.iter() {
+        if *interaction == Interaction::Hovered {
+            if let Some(callback) = &ability_button.secondary_callback {
+                press_button(commands, callback, ability_button.data);
             }
         }
     }
 }
 
+/// Presses ability buttons with their hotkey, and cancels the ability being targeted
+fn hotkey_system(
+    commands: &mut Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    hotkeys: Res<Hotkeys>,
+    mut ability: ResMut<CurrentAbility>,
+    button_query: Query<&AbilityButton>,
+) {
+    if keyboard_input.just_pressed(hotkeys.cancel) {
+        ability.ability = Ability::Select;
+        return;
+    }
+
+    for ability_button in button_query.iter() {
+        if let Some(key) = ability_button.hotkey {
+            if keyboard_input.just_pressed(key) && !ability_button.disabled {
+                press_button(commands, &ability_button.callback, ability_button.data);
+                return;
+            }
+        }
+    }
+}
+
+/// Shows the cooldown and autocast of ability buttons, and greys them out while they can't be
+/// cast
+fn update_ability_button_states(
+    time: Res<ControlledTime>,
+    assets: Res<UiAssetsResource>,
+    registry: Res<AbilityRegistry>,
+    mut button_query: Query<(&mut AbilityButton, &Children, &mut Handle<ColorMaterial>)>,
+    mut text_query: Query<&mut Text>,
+    caster_query: Query<(&Selectable, &UnitAbilities, Option<&Energy>)>,
+) {
+    for (mut ability_button, children, mut material) in button_query.iter_mut() {
+        let definition = match ability_button.data.ability.and_then(|id| registry.get(id)) {
+            Some(definition) => definition,
+            None => continue,
+        };
+
+        // The button is usable as soon as one of the selected units can cast it
+        let state = group_cast_state(
+            caster_query
+                .iter()
+                .filter(|(selectable, abilities, _)| {
+                    selectable.selected && abilities.abilities.contains(&definition.id)
+                })
+                .map(|(_, abilities, energy)| {
+                    abilities.cast_state(definition, energy, time.seconds_since_startup)
+                }),
+        );
+        let state = match state {
+            Some(state) => state,
+            None => continue,
+        };
+
+        let mut label = match state {
+            CastState::Ready => ability_button.name.clone(),
+            CastState::Cooldown(seconds) => {
+                format!("{} ({:.0}s)", ability_button.name, seconds.ceil())
+            }
+            CastState::NotEnoughEnergy => format!("{} (no energy)", ability_button.name),
+        };
+        if caster_query.iter().any(|(selectable, abilities, _)| {
+            selectable.selected && abilities.is_autocast(definition.id)
+        }) {
+            label.push_str(" (auto)");
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.value != label {
+                    text.value = label.clone();
+                }
+            }
+        }
+
+        let disabled = state != CastState::Ready;
+        if disabled != ability_button.disabled {
+            ability_button.disabled = disabled;
+            *material = if disabled {
+                assets.material_disabled.clone()
+            } else {
+                assets.material.clone()
+            };
+        }
+    }
+}
+
 struct PickingBlocker;
 fn block_picking_under_blockers(
     mut pick_state: ResMut<PickState>,
@@ -266,9 +465,20 @@ fn init_ability_text(commands: &mut Commands, assets: Res<UiAssetsResource>) {
         });
 }
 
-fn ability_text_update(ability: Res<CurrentAbility>, mut query: Query<(&mut Text, &AbilityText)>) {
+fn ability_text_update(
+    ability: Res<CurrentAbility>,
+    registry: Res<AbilityRegistry>,
+    mut query: Query<(&mut Text, &AbilityText)>,
+) {
     for (mut text, _tag) in query.iter_mut() {
-        text.value = format!("Ability: {}", ability.ability);
+        text.value = match ability.ability {
+            // Show the name of the ability instead of its id
+            Ability::Cast { ability: id } => match registry.get(id) {
+                Some(definition) => format!("Ability: {}", definition.name),
+                None => format!("Ability: {}", ability.ability),
+            },
+            _ => format!("Ability: {}", ability.ability),
+        };
     }
 }
 
@@ -278,10 +488,14 @@ impl Plugin for UIPlugin {
         app.init_resource::<UiAssetsResource>()
             .init_resource::<AvailableButtons>()
             .init_resource::<DisplayedButtons>()
+            .init_resource::<Hotkeys>()
             .add_startup_system(init_ability_text)
             .add_system(ability_text_update)
             .add_system(block_picking_under_blockers)
             .add_system(button_system)
+            .add_system(secondary_button_system)
+            .add_system(hotkey_system)
+            .add_system(update_ability_button_states)
             .add_system(change_displayed_buttons);
     }
 }
@@ -1,4 +1,8 @@
-use crate::systems::time::*;
+use crate::systems::{
+    death::Dying,
+    status_effect::{Stat, StatusEffects},
+    time::*,
+};
 use bevy::{math::Vec3, prelude::*};
 
 pub struct Unit {
@@ -28,21 +32,25 @@ impl TargetPosition {
 // Moves towards the target while it's not selected
 fn unit_movement(
     time: Res<ControlledTime>,
-    mut query: Query<(
-        &Unit,
-        &mut TargetPosition,
-        &mut Transform,
-        Entity,
-        &UnitSize,
-    )>,
+    mut query: Query<
+        (
+            &Unit,
+            &mut TargetPosition,
+            &mut Transform,
+            Entity,
+            &UnitSize,
+            Option<&StatusEffects>,
+        ),
+        Without<Dying>,
+    >,
 ) {
     // TODO Do something to divide by space or something
     let mut unit_positions = Vec::new();
-    for (unit, _, transform, entity, size) in query.iter_mut() {
+    for (unit, _, transform, entity, size, _) in query.iter_mut() {
         unit_positions.push((entity, transform.translation, unit.social_distance * size.0));
     }
 
-    for (unit, mut target, mut transform, entity, size) in query.iter_mut() {
+    for (unit, mut target, mut transform, entity, size, effects) in query.iter_mut() {
         let translation = transform.translation;
         let mut velocity = Vec3::zero();
 
@@ -69,13 +77,20 @@ fn unit_movement(
         separation.y = 0.;
         velocity += separation;
 
-        // Move towards target
+        // Move towards target, unless stunned
+        let speed = effects.map_or(unit.speed, |effects| {
+            if effects.is_stunned() {
+                0.
+            } else {
+                unit.speed * effects.stat_multiplier(Stat::Speed)
+            }
+        });
         if let Some(target_pos) = target.pos {
             let mut direction = target_pos - transform.translation;
             direction.y = 0.;
 
             if direction.length() > 0.3 + units_nearby as f32 {
-                let direction = direction.normalize() * unit.speed * time.delta_seconds;
+                let direction = direction.normalize() * speed * time.delta_seconds;
                 velocity += direction;
             } else {
                 // When we reach the target, remove it
@@ -94,6 +109,26 @@ fn unit_movement(
     }
 }
 
+/// How fast the unit actually moved during the last frame, whatever moved it
+#[derive(Default)]
+pub struct Velocity {
+    pub velocity: Vec3,
+    previous_position: Option<Vec3>,
+}
+
+fn track_velocity(time: Res<ControlledTime>, mut query: Query<(&mut Velocity, &Transform)>) {
+    if time.delta_seconds <= 0. {
+        return;
+    }
+
+    for (mut velocity, transform) in query.iter_mut() {
+        if let Some(previous_position) = velocity.previous_position {
+            velocity.velocity = (transform.translation - previous_position) / time.delta_seconds;
+        }
+        velocity.previous_position = Some(transform.translation);
+    }
+}
+
 pub struct UnitSize(pub f32);
 impl Default for UnitSize {
     fn default() -> Self {
@@ -101,9 +136,24 @@ impl Default for UnitSize {
     }
 }
 
+/// Broad role of a unit, used e.g. by targeting policies
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnitClass {
+    Infantry,
+    Drone,
+    Support,
+    Swarmer,
+}
+impl Default for UnitClass {
+    fn default() -> Self {
+        UnitClass::Infantry
+    }
+}
+
 pub struct UnitPlugin;
 impl Plugin for UnitPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(unit_movement.system());
+        app.add_system(unit_movement.system())
+            .add_system(track_velocity.system());
     }
 }
@@ -1,5 +1,5 @@
 use crate::helpers::movement::*;
-use crate::systems::{camera::*, time::*};
+use crate::systems::{camera::*, death::Dying, time::*};
 use bevy::{input::mouse::MouseMotion, math::Vec3, prelude::*, render::camera::Camera};
 
 #[derive(Default)]
@@ -41,7 +41,7 @@ fn wasd_walk_for_camera_holder(
     time: Res<ControlledTime>,
     keyboard_input: Res<Input<KeyCode>>,
     camera_query: Query<(&Camera, &CameraFollow)>,
-    mut can_have_camera_query: Query<(&mut Walker, &CanHaveCamera, &mut Transform)>,
+    mut can_have_camera_query: Query<(&mut Walker, &CanHaveCamera, &mut Transform), Without<Dying>>,
 ) {
     for (_, camera_follow) in camera_query.iter() {
         if let Some(following) = camera_follow.entity {